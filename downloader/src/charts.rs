@@ -0,0 +1,408 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use encoding_rs::SHIFT_JIS;
+use log::warn;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 谱面文件的扩展名
+pub const CHART_EXTENSIONS: &[&str] = &["bms", "bme", "bml", "pms", "bmson"];
+
+/// 谱面文件的大小上限，超过的文件不是正常的谱面，跳过
+pub const MAX_CHART_SIZE: u64 = 64 * 1024 * 1024;
+
+/// 作品包中的一个谱面，对应 events/<key>/charts.jsonl 中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChartRecord {
+    /// 所属作品的编号
+    pub no: String,
+    /// 谱面文件在作品包中的路径
+    pub file: String,
+    pub md5: String,
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playlevel: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm_min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm_max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<u32>,
+}
+
+/// 从谱面内容中读取的信息
+#[derive(Debug, Default)]
+struct ChartInfo {
+    title: Option<String>,
+    subtitle: Option<String>,
+    artist: Option<String>,
+    playlevel: Option<String>,
+    difficulty: Option<u8>,
+    bpm_min: Option<f64>,
+    bpm_max: Option<f64>,
+    notes: Option<u32>,
+}
+
+impl ChartInfo {
+    fn add_bpm(&mut self, bpm: f64) {
+        if bpm <= 0.0 || !bpm.is_finite() {
+            return;
+        }
+        self.bpm_min = Some(self.bpm_min.map_or(bpm, |min| min.min(bpm)));
+        self.bpm_max = Some(self.bpm_max.map_or(bpm, |max| max.max(bpm)));
+    }
+}
+
+pub fn is_chart_file(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| CHART_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// 读取谱面文件，超过大小上限时跳过
+pub fn read_chart_file(path: &Path) -> Result<Option<Vec<u8>>> {
+    let size = fs::metadata(path)
+        .with_context(|| format!("无法读取文件: {:?}", path))?
+        .len();
+    if size > MAX_CHART_SIZE {
+        warn!("谱面文件过大，跳过: {:?}", path);
+        return Ok(None);
+    }
+    fs::read(path)
+        .map(Some)
+        .with_context(|| format!("无法读取文件: {:?}", path))
+}
+
+/// BMS文件通常为Shift_JIS编码，也有少数为UTF-8
+fn decode_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.trim_start_matches('\u{feff}').to_string(),
+        Err(_) => SHIFT_JIS.decode(bytes).0.into_owned(),
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// 解析BMS格式的谱面
+///
+/// `#RANDOM` 分支只计算 `#IF 1` 的内容，因此随机谱面的物量只是其中一种结果。
+fn parse_bms(text: &str) -> ChartInfo {
+    let mut info = ChartInfo::default();
+    let mut bpm_definitions: HashMap<String, f64> = HashMap::new();
+    let mut extended_bpm_refs: Vec<String> = Vec::new();
+    let mut lnobj: Option<String> = None;
+    let mut visible_notes = 0u32;
+    let mut long_note_objects = 0u32;
+    let mut visible_objects: Vec<String> = Vec::new();
+    // 每一层 #IF 是否生效
+    let mut conditions: Vec<bool> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        let Some(line) = line.strip_prefix('#') else {
+            continue;
+        };
+        let upper = line.to_ascii_uppercase();
+
+        if upper.starts_with("RANDOM")
+            || upper.starts_with("SETRANDOM")
+            || upper.starts_with("ENDRANDOM")
+        {
+            continue;
+        }
+        if let Some(value) = upper.strip_prefix("IF") {
+            conditions.push(value.trim() == "1");
+            continue;
+        }
+        if upper.starts_with("ENDIF") {
+            conditions.pop();
+            continue;
+        }
+        if conditions.iter().any(|active| !active) {
+            continue;
+        }
+
+        // 通道数据: #mmmcc:data
+        if let Some((head, data)) = line.split_once(':')
+            && head.len() == 5
+            && head.is_ascii()
+            && head[..3].chars().all(|c| c.is_ascii_digit())
+        {
+            let channel = head[3..].to_ascii_uppercase();
+            let objects: Vec<String> = data
+                .trim()
+                .as_bytes()
+                .chunks(2)
+                .filter(|pair| pair.len() == 2)
+                .map(|pair| String::from_utf8_lossy(pair).to_ascii_uppercase())
+                .filter(|pair| pair != "00")
+                .collect();
+            match channel.as_bytes() {
+                b"03" => {
+                    for object in &objects {
+                        if let Ok(bpm) = u8::from_str_radix(object, 16) {
+                            info.add_bpm(f64::from(bpm));
+                        }
+                    }
+                }
+                b"08" => extended_bpm_refs.extend(objects),
+                [b'1' | b'2', b'1'..=b'9'] => {
+                    visible_notes += objects.len() as u32;
+                    visible_objects.extend(objects);
+                }
+                [b'5' | b'6', b'1'..=b'9'] => long_note_objects += objects.len() as u32,
+                _ => {}
+            }
+            continue;
+        }
+
+        let (key, value) = match line.find(char::is_whitespace) {
+            Some(idx) => (&upper[..idx], &line[idx..]),
+            None => (upper.as_str(), ""),
+        };
+        match key {
+            "TITLE" => info.title = non_empty(value),
+            "SUBTITLE" => info.subtitle = non_empty(value),
+            "ARTIST" => info.artist = non_empty(value),
+            "PLAYLEVEL" => info.playlevel = non_empty(value),
+            "DIFFICULTY" => info.difficulty = value.trim().parse().ok(),
+            "BPM" => {
+                if let Ok(bpm) = value.trim().parse() {
+                    info.add_bpm(bpm);
+                }
+            }
+            "LNOBJ" => lnobj = non_empty(value).map(|v| v.to_ascii_uppercase()),
+            _ if (key.len() == 5 && key.starts_with("BPM"))
+                || (key.len() == 7 && key.starts_with("EXBPM")) =>
+            {
+                let id = key[key.len() - 2..].to_string();
+                if let Ok(bpm) = value.trim().parse() {
+                    bpm_definitions.insert(id, bpm);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for id in &extended_bpm_refs {
+        if let Some(bpm) = bpm_definitions.get(id) {
+            info.add_bpm(*bpm);
+        }
+    }
+
+    // #LNOBJ 指定的音符是长音符的结尾，不单独计数
+    if let Some(lnobj) = &lnobj {
+        let ends = visible_objects.iter().filter(|o| *o == lnobj).count() as u32;
+        visible_notes -= ends;
+    }
+    info.notes = Some(visible_notes + long_note_objects / 2);
+    info
+}
+
+/// 解析bmson格式的谱面
+fn parse_bmson(text: &str) -> Result<ChartInfo> {
+    let json: serde_json::Value = serde_json::from_str(text).context("解析bmson失败")?;
+    let field = |name: &str| json["info"][name].as_str().and_then(non_empty);
+
+    let mut info = ChartInfo {
+        title: field("title"),
+        subtitle: field("subtitle"),
+        artist: field("artist"),
+        playlevel: json["info"]["level"]
+            .as_i64()
+            .map(|level| level.to_string()),
+        ..Default::default()
+    };
+    if let Some(bpm) = json["info"]["init_bpm"].as_f64() {
+        info.add_bpm(bpm);
+    }
+    for event in json["bpm_events"].as_array().into_iter().flatten() {
+        if let Some(bpm) = event["bpm"].as_f64() {
+            info.add_bpm(bpm);
+        }
+    }
+
+    // x 为0的音符是背景音
+    let notes = json["sound_channels"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|channel| channel["notes"].as_array().into_iter().flatten())
+        .filter(|note| note["x"].as_u64().unwrap_or_default() > 0)
+        .count();
+    info.notes = Some(notes as u32);
+    Ok(info)
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 计算谱面文件的哈希并读取谱面信息
+pub fn inspect_chart(no: &str, file: &str, bytes: &[u8]) -> ChartRecord {
+    let text = decode_text(bytes);
+    let is_bmson = file.to_ascii_lowercase().ends_with(".bmson");
+    let info = if is_bmson {
+        parse_bmson(&text).unwrap_or_else(|e| {
+            warn!("{}: {}", file, e);
+            ChartInfo::default()
+        })
+    } else {
+        parse_bms(&text)
+    };
+
+    ChartRecord {
+        no: no.to_string(),
+        file: file.to_string(),
+        md5: hex(&Md5::digest(bytes)),
+        sha256: hex(&Sha256::digest(bytes)),
+        title: info.title,
+        subtitle: info.subtitle,
+        artist: info.artist,
+        playlevel: info.playlevel,
+        difficulty: info.difficulty,
+        bpm_min: info.bpm_min,
+        bpm_max: info.bpm_max,
+        notes: info.notes,
+    }
+}
+
+/// 事件文件对应的谱面记录文件: events/BOFTT.toml -> events/BOFTT/charts.jsonl
+pub fn charts_path(event_path: &Path) -> PathBuf {
+    let stem = event_path.file_stem().unwrap_or_default();
+    event_path.with_file_name(stem).join("charts.jsonl")
+}
+
+pub fn load_charts(path: &Path) -> Result<Vec<ChartRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+    let mut charts = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<ChartRecord>(line) {
+            Ok(chart) => charts.push(chart),
+            Err(e) => warn!("跳过无法解析的谱面记录 {:?}:{}: {}", path, i + 1, e),
+        }
+    }
+    Ok(charts)
+}
+
+pub fn save_charts(path: &Path, charts: &[ChartRecord]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("创建目录失败: {:?}", parent))?;
+    }
+    let mut content = String::new();
+    for chart in charts {
+        content.push_str(&serde_json::to_string(chart)?);
+        content.push('\n');
+    }
+    fs::write(path, content).with_context(|| format!("写入文件失败: {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_fields() {
+        let info = parse_bms(
+            "#PLAYER 1\n#TITLE  Song [ANOTHER] \n#SUBTITLE -7keys-\n#ARTIST a / b\n\
+             #PLAYLEVEL 12\n#DIFFICULTY 4\n#BPM 150\n",
+        );
+        assert_eq!(info.title.as_deref(), Some("Song [ANOTHER]"));
+        assert_eq!(info.subtitle.as_deref(), Some("-7keys-"));
+        assert_eq!(info.artist.as_deref(), Some("a / b"));
+        assert_eq!(info.playlevel.as_deref(), Some("12"));
+        assert_eq!(info.difficulty, Some(4));
+        assert_eq!((info.bpm_min, info.bpm_max), (Some(150.0), Some(150.0)));
+        assert_eq!(info.notes, Some(0));
+    }
+
+    #[test]
+    fn long_note_channels() {
+        // 5x/6x 通道的开始和结尾成对出现，算一个音符
+        let info = parse_bms("#00111:01010101\n#00151:0101\n#00261:02000200\n#00101:0303\n");
+        assert_eq!(info.notes, Some(4 + 2));
+    }
+
+    #[test]
+    fn lnobj_ends() {
+        let info = parse_bms("#LNOBJ zz\n#00111:0101ZZ01\n#00216:0Zzz\n");
+        assert_eq!(info.notes, Some(3 + 1));
+    }
+
+    #[test]
+    fn random_branches() {
+        let info = parse_bms(
+            "#RANDOM 2\n#IF 1\n#00111:0101\n#ENDIF\n#IF 2\n#TITLE other\n#00111:010101\n\
+             #ENDIF\n#ENDRANDOM\n#TITLE main\n#00112:01\n",
+        );
+        assert_eq!(info.title.as_deref(), Some("main"));
+        assert_eq!(info.notes, Some(3));
+    }
+
+    #[test]
+    fn bpm_range() {
+        let info =
+            parse_bms("#BPM 150\n#BPM01 75.5\n#EXBPM02 300\n#BPM03 999\n#00103:3C\n#00108:0102\n");
+        assert_eq!(info.bpm_min, Some(60.0));
+        assert_eq!(info.bpm_max, Some(300.0));
+    }
+
+    #[test]
+    fn bmson() {
+        let info = parse_bmson(
+            r#"{
+                "info": {"title": "Song", "artist": "a", "level": 12, "init_bpm": 150},
+                "bpm_events": [{"y": 960, "bpm": 75}, {"y": 1920, "bpm": 300}],
+                "sound_channels": [
+                    {"name": "a.wav", "notes": [{"x": 1, "y": 0}, {"x": 0, "y": 0}]},
+                    {"name": "b.wav", "notes": [{"x": 8, "y": 240}, {"y": 480}]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(info.title.as_deref(), Some("Song"));
+        assert_eq!(info.subtitle, None);
+        assert_eq!(info.playlevel.as_deref(), Some("12"));
+        assert_eq!((info.bpm_min, info.bpm_max), (Some(75.0), Some(300.0)));
+        assert_eq!(info.notes, Some(2));
+        assert!(parse_bmson("#TITLE not json").is_err());
+    }
+
+    #[test]
+    fn shift_jis_chart() {
+        let (bytes, _, _) = SHIFT_JIS.encode("#TITLE ソ\r\n#00111:01\r\n");
+        let record = inspect_chart("1", "song/ソ.bms", &bytes);
+        assert_eq!(record.title.as_deref(), Some("ソ"));
+        assert_eq!(record.notes, Some(1));
+        assert_eq!(record.md5, hex(&Md5::digest(&bytes)));
+    }
+}
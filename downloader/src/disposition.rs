@@ -0,0 +1,292 @@
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+/// 把参数值按 RFC 2616 拆分：token 或带反斜杠转义的 quoted-string
+///
+/// 返回参数名 (小写) 和值的原始字节，没有disposition类型时也能解析。
+fn parameters(value: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut params = Vec::new();
+    let mut pos = 0;
+    let skip_whitespace = |pos: &mut usize| {
+        while value.get(*pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            *pos += 1;
+        }
+    };
+
+    while pos < value.len() {
+        skip_whitespace(&mut pos);
+        let name_start = pos;
+        while pos < value.len() && value[pos] != b'=' && value[pos] != b';' {
+            pos += 1;
+        }
+        let name = String::from_utf8_lossy(&value[name_start..pos])
+            .trim()
+            .to_ascii_lowercase();
+        if value.get(pos) != Some(&b'=') {
+            // disposition类型或没有值的参数
+            pos += 1;
+            continue;
+        }
+        pos += 1;
+        skip_whitespace(&mut pos);
+
+        let mut param = Vec::new();
+        if value.get(pos) == Some(&b'"') {
+            pos += 1;
+            while pos < value.len() && value[pos] != b'"' {
+                if value[pos] == b'\\' && pos + 1 < value.len() {
+                    pos += 1;
+                }
+                param.push(value[pos]);
+                pos += 1;
+            }
+            // 跳过结尾的引号和下一个分号之前的内容
+            while pos < value.len() && value[pos] != b';' {
+                pos += 1;
+            }
+        } else {
+            while pos < value.len() && value[pos] != b';' {
+                param.push(value[pos]);
+                pos += 1;
+            }
+            param.truncate(param.trim_ascii_end().len());
+        }
+        pos += 1;
+        params.push((name, param));
+    }
+    params
+}
+
+/// 解码 %XX，格式错误时返回 None
+fn percent_decode(value: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut bytes = value.iter();
+    while let Some(&b) = bytes.next() {
+        if b == b'%' {
+            let hex = [*bytes.next()?, *bytes.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            decoded.push(b);
+        }
+    }
+    Some(decoded)
+}
+
+/// RFC 5987 的扩展值: charset'language'percent-encoded
+fn decode_ext_value(value: &[u8]) -> Option<String> {
+    let value = std::str::from_utf8(value).ok()?;
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?.trim();
+    let _language = parts.next()?;
+    let encoded = parts.next()?;
+    let encoding = Encoding::for_label(charset.as_bytes())?;
+    let bytes = percent_decode(encoded.as_bytes())?;
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes)
+        .map(|text| text.into_owned())
+}
+
+/// 普通的 filename 参数
+///
+/// 规范上只能是ISO-8859-1，实际上很多服务器直接发送UTF-8或Shift_JIS，
+/// 也有服务器像URL一样进行百分号编码。
+fn decode_plain_value(value: &[u8]) -> Option<String> {
+    if value.is_ascii() && value.contains(&b'%') {
+        let decoded = percent_decode(value).filter(|bytes| !bytes.is_ascii());
+        if let Some(text) = decoded.and_then(|bytes| String::from_utf8(bytes).ok()) {
+            return Some(text);
+        }
+    }
+    [UTF_8, SHIFT_JIS, WINDOWS_1252]
+        .iter()
+        .find_map(|encoding| {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(value)
+                .map(|text| text.into_owned())
+        })
+}
+
+/// 只保留路径的最后一部分并去掉控制字符，"." 和 ".." 视为无效
+fn sanitize(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next()?;
+    let name: String = name.chars().filter(|c| !c.is_control()).collect();
+    let name = name.trim();
+    (!name.is_empty() && name != "." && name != "..").then(|| name.to_string())
+}
+
+/// 按 RFC 6266 和 RFC 5987 从 Content-Disposition 头的原始字节中解析文件名
+///
+/// 优先使用 `filename*`，无法解码时使用 `filename`。
+pub fn parse_filename(value: &[u8]) -> Option<String> {
+    let params = parameters(value);
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_slice())
+    };
+    param("filename*")
+        .and_then(decode_ext_value)
+        .and_then(|name| sanitize(&name))
+        .or_else(|| {
+            param("filename")
+                .and_then(decode_plain_value)
+                .and_then(|name| sanitize(&name))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_filename;
+
+    fn parse(value: &str) -> Option<String> {
+        parse_filename(value.as_bytes())
+    }
+
+    #[test]
+    fn plain_filename() {
+        assert_eq!(
+            parse("attachment; filename=\"BOF2011_pack.zip\""),
+            Some("BOF2011_pack.zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename=pack.rar"),
+            Some("pack.rar".into())
+        );
+        assert_eq!(
+            parse("inline;filename=pack.rar ;size=10"),
+            Some("pack.rar".into())
+        );
+        assert_eq!(
+            parse("attachment; filename = \"a.zip\""),
+            Some("a.zip".into())
+        );
+        assert_eq!(parse("filename=\"a.zip\""), Some("a.zip".into()));
+        assert_eq!(parse("attachment"), None);
+    }
+
+    #[test]
+    fn quoted_string() {
+        assert_eq!(
+            parse(r#"attachment; filename="a \"b\".zip""#),
+            Some("a \"b\".zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename=\"a;b.zip\""),
+            Some("a;b.zip".into())
+        );
+    }
+
+    #[test]
+    fn parameter_names() {
+        assert_eq!(
+            parse("attachment; myfilename=\"wrong.zip\"; filename=\"right.zip\""),
+            Some("right.zip".into())
+        );
+        assert_eq!(
+            parse("ATTACHMENT; FileName=\"a.zip\""),
+            Some("a.zip".into())
+        );
+    }
+
+    #[test]
+    fn ext_value_preferred() {
+        // Google Drive
+        assert_eq!(
+            parse(
+                "attachment; filename=\"???.zip\"; filename*=UTF-8''%E3%82%AB%E3%83%A1%E3%81%95%E3%82%93.zip"
+            ),
+            Some("カメさん.zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename*=UTF-8''%E3%82%AB%E3%83%A1.zip; filename=\"_.zip\""),
+            Some("カメ.zip".into())
+        );
+        // Dropbox
+        assert_eq!(
+            parse("attachment; filename=\"pack v1.1.zip\"; filename*=UTF-8''pack%20v1.1.zip"),
+            Some("pack v1.1.zip".into())
+        );
+    }
+
+    #[test]
+    fn ext_value_charsets() {
+        assert_eq!(
+            parse("attachment; filename*=iso-8859-1'en'%A3%20rates.zip"),
+            Some("£ rates.zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename*=utf-8'ja'%E6%9B%B2.zip"),
+            Some("曲.zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename*=Shift_JIS''%83J%83%81.zip"),
+            Some("カメ.zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename*=\"UTF-8''%E6%9B%B2.zip\""),
+            Some("曲.zip".into())
+        );
+    }
+
+    #[test]
+    fn invalid_ext_value_falls_back() {
+        assert_eq!(
+            parse("attachment; filename*=UTF-8''%E3%8; filename=\"fallback.zip\""),
+            Some("fallback.zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename*=unknown''a.zip; filename=\"fallback.zip\""),
+            Some("fallback.zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename*=UTF-8''%FF%FE.zip; filename=b.zip"),
+            Some("b.zip".into())
+        );
+    }
+
+    #[test]
+    fn raw_bytes() {
+        assert_eq!(
+            parse("attachment; filename=\"カメさん.zip\""),
+            Some("カメさん.zip".into())
+        );
+        let mut shift_jis = b"attachment; filename=\"".to_vec();
+        shift_jis.extend_from_slice(b"\x83J\x83\x81.lzh\"");
+        assert_eq!(parse_filename(&shift_jis), Some("カメ.lzh".into()));
+    }
+
+    #[test]
+    fn percent_encoded_plain_filename() {
+        assert_eq!(
+            parse("attachment; filename=\"%E3%82%AB%E3%83%A1.zip\""),
+            Some("カメ.zip".into())
+        );
+        assert_eq!(
+            parse("attachment; filename=\"100%.zip\""),
+            Some("100%.zip".into())
+        );
+    }
+
+    #[test]
+    fn path_components() {
+        assert_eq!(
+            parse("attachment; filename=\"../../etc/passwd\""),
+            Some("passwd".into())
+        );
+        assert_eq!(
+            parse(r#"attachment; filename="C:\\temp\\evil.exe""#),
+            Some("evil.exe".into())
+        );
+        assert_eq!(
+            parse("attachment; filename*=UTF-8''..%2F..%2Fa.zip"),
+            Some("a.zip".into())
+        );
+        assert_eq!(parse("attachment; filename=\"..\""), None);
+        assert_eq!(parse("attachment; filename=\"dir/\""), None);
+        assert_eq!(
+            parse("attachment; filename=\"a\tb.zip\""),
+            Some("ab.zip".into())
+        );
+    }
+}
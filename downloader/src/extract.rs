@@ -0,0 +1,546 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use encoding_rs::{EUC_KR, Encoding, GBK, SHIFT_JIS, UTF_8};
+use log::{info, warn};
+
+use crate::filetype::{self, FileType};
+
+/// 解压后的总大小上限
+const MAX_EXTRACTED_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+
+/// 解压后的大小最多为压缩包的多少倍，WAV和BMS的压缩率远低于这个值
+const MAX_RATIO: u64 = 100;
+
+/// 压缩包中的文件数上限
+const MAX_FILES: usize = 50_000;
+
+/// LZH扩展头中的文件名和目录名
+const LZH_EXT_FILENAME: u8 = 0x01;
+const LZH_EXT_PATH: u8 = 0x02;
+
+/// 解压的剩余额度，超过时视为压缩炸弹并中止
+struct Budget {
+    remaining: u64,
+    limit: u64,
+    files: usize,
+}
+
+impl Budget {
+    fn new(archive_size: u64) -> Self {
+        let limit = archive_size
+            .saturating_mul(MAX_RATIO)
+            .min(MAX_EXTRACTED_SIZE);
+        Budget {
+            remaining: limit,
+            limit,
+            files: 0,
+        }
+    }
+
+    fn add_file(&mut self) -> Result<()> {
+        self.files += 1;
+        if self.files > MAX_FILES {
+            return Err(anyhow::anyhow!(
+                "压缩包中的文件超过 {} 个，可能是压缩炸弹",
+                MAX_FILES
+            ));
+        }
+        Ok(())
+    }
+
+    fn take(&mut self, size: u64) -> Result<()> {
+        self.remaining = self.remaining.checked_sub(size).ok_or_else(|| {
+            anyhow::anyhow!("解压后的大小超过上限 {} 字节，可能是压缩炸弹", self.limit)
+        })?;
+        Ok(())
+    }
+
+    /// 边解压边计数，不相信压缩包中记录的大小
+    fn copy(&mut self, reader: &mut dyn Read, writer: &mut dyn Write) -> Result<u64> {
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut total = 0u64;
+        loop {
+            let n = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e).context("解压失败"),
+            };
+            self.take(n as u64)?;
+            writer.write_all(&buffer[..n]).context("写入文件失败")?;
+            total += n as u64;
+        }
+        Ok(total)
+    }
+}
+
+/// 所有文件名都能无错误地解码时使用该编码，依次尝试UTF-8、Shift_JIS、GBK、EUC-KR
+///
+/// 日文Windows制作的压缩包通常没有UTF-8标记，文件名为Shift_JIS。
+pub(crate) fn detect_encoding<'a>(
+    names: impl Iterator<Item = &'a [u8]> + Clone,
+) -> &'static Encoding {
+    [UTF_8, SHIFT_JIS, GBK, EUC_KR]
+        .into_iter()
+        .find(|encoding| {
+            names.clone().all(|name| {
+                encoding
+                    .decode_without_bom_handling_and_without_replacement(name)
+                    .is_some()
+            })
+        })
+        .unwrap_or(SHIFT_JIS)
+}
+
+/// 压缩包中的路径转为解压目录中的相对路径
+///
+/// 去掉开头的 "/" 和控制字符，包含 ".." 或盘符的路径返回 None (zip slip)。
+fn safe_relative_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        let part: String = part.chars().filter(|c| !c.is_control()).collect();
+        match part.trim() {
+            "" | "." => continue,
+            ".." => return None,
+            part if part.contains(':') => return None,
+            part => path.push(part),
+        }
+    }
+    (path.components().next().is_some()).then_some(path)
+}
+
+/// 解压目录中的目标路径，不安全的路径跳过并输出警告
+fn target_path(dest: &Path, name: &str) -> Option<PathBuf> {
+    match safe_relative_path(name) {
+        Some(path) => Some(dest.join(path)),
+        None => {
+            warn!("跳过不安全的路径: {:?}", name);
+            None
+        }
+    }
+}
+
+fn create_file(path: &Path) -> Result<fs::File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("创建目录失败: {:?}", parent))?;
+    }
+    fs::File::create(path).with_context(|| format!("创建文件失败: {:?}", path))
+}
+
+fn extract_zip(archive_path: &Path, dest: &Path, budget: &mut Budget) -> Result<()> {
+    let file = fs::File::open(archive_path)
+        .with_context(|| format!("无法打开文件: {:?}", archive_path))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("无法读取ZIP文件: {:?}", archive_path))?;
+
+    let raw_names: Vec<Vec<u8>> = (0..archive.len())
+        .map(|i| Ok(archive.by_index_raw(i)?.name_raw().to_vec()))
+        .collect::<Result<_>>()?;
+    let encoding = detect_encoding(raw_names.iter().map(Vec::as_slice));
+    info!("ZIP文件名编码: {}", encoding.name());
+
+    for (i, raw_name) in raw_names.iter().enumerate() {
+        let mut entry = archive
+            .by_index(i)
+            .with_context(|| format!("无法读取ZIP中的文件: {:?}", archive_path))?;
+        let name = encoding.decode_without_bom_handling(raw_name).0;
+        let Some(path) = target_path(dest, &name) else {
+            continue;
+        };
+        budget.add_file()?;
+        if entry.is_dir() {
+            fs::create_dir_all(&path).with_context(|| format!("创建目录失败: {:?}", path))?;
+            continue;
+        }
+        // 符号链接按普通文件写出其内容，不创建链接
+        let mut file = create_file(&path)?;
+        budget
+            .copy(&mut entry, &mut file)
+            .with_context(|| format!("解压失败: {}", name))?;
+    }
+    Ok(())
+}
+
+fn extract_7z(archive_path: &Path, dest: &Path, budget: &mut Budget) -> Result<()> {
+    let mut archive =
+        sevenz_rust2::ArchiveReader::open(archive_path, sevenz_rust2::Password::empty())
+            .map_err(|e| anyhow::anyhow!("无法读取7z文件: {:?}: {}", archive_path, e))?;
+
+    // 回调只能返回 sevenz_rust2::Error，自己的错误保存在这里
+    let mut failure = None;
+    let result = archive.for_each_entries(|entry, reader| {
+        let mut step = || -> Result<()> {
+            // 固实压缩的文件共用一个数据流，跳过的文件也要读完，否则后面的文件会错位
+            let path = target_path(dest, entry.name()).filter(|_| !entry.is_anti_item());
+            let Some(path) = path else {
+                budget
+                    .copy(reader, &mut io::sink())
+                    .with_context(|| format!("解压失败: {}", entry.name()))?;
+                return Ok(());
+            };
+            budget.add_file()?;
+            if entry.is_directory() {
+                fs::create_dir_all(&path).with_context(|| format!("创建目录失败: {:?}", path))?;
+                return Ok(());
+            }
+            let mut file = create_file(&path)?;
+            budget
+                .copy(reader, &mut file)
+                .with_context(|| format!("解压失败: {}", entry.name()))?;
+            Ok(())
+        };
+        // 回调返回 Ok(false) 只会跳到下一个数据块，需要返回错误才能中止解压
+        step().map(|()| true).map_err(|e| {
+            let message = format!("{:#}", e);
+            failure = Some(e);
+            sevenz_rust2::Error::Other(message.into())
+        })
+    });
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    result.map_err(|e| anyhow::anyhow!("解压7z文件失败: {:?}: {}", archive_path, e))
+}
+
+fn extract_rar(archive_path: &Path, dest: &Path, budget: &mut Budget) -> Result<()> {
+    let mut archive = unrar::Archive::new(archive_path)
+        .open_for_processing()
+        .map_err(|e| anyhow::anyhow!("无法读取RAR文件: {:?}: {}", archive_path, e))?;
+    while let Some(header) = archive
+        .read_header()
+        .map_err(|e| anyhow::anyhow!("读取RAR文件失败: {:?}: {}", archive_path, e))?
+    {
+        let entry = header.entry();
+        let name = entry.filename.to_string_lossy().into_owned();
+        if entry.is_split() {
+            return Err(anyhow::anyhow!("不支持分卷RAR压缩包: {:?}", archive_path));
+        }
+        // Unix上创建的压缩包可能含有符号链接
+        let is_symlink = entry.file_attr & 0o170000 == 0o120000;
+        let path = target_path(dest, &name).filter(|_| !is_symlink);
+        let Some(path) = path else {
+            archive = header.skip().context("读取RAR文件失败")?;
+            continue;
+        };
+        budget.add_file()?;
+        if entry.is_directory() {
+            fs::create_dir_all(&path).with_context(|| format!("创建目录失败: {:?}", path))?;
+            archive = header.skip().context("读取RAR文件失败")?;
+            continue;
+        }
+
+        // unrar直接写文件，只能先按记录的大小检查，写完后再按实际大小检查
+        let unpacked_size = entry.unpacked_size;
+        budget.take(unpacked_size)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("创建目录失败: {:?}", parent))?;
+        }
+        archive = header
+            .extract_to(&path)
+            .with_context(|| format!("解压失败: {}", name))?;
+        let actual = fs::metadata(&path)
+            .with_context(|| format!("无法读取文件: {:?}", path))?
+            .len();
+        budget.take(actual.saturating_sub(unpacked_size))?;
+    }
+    Ok(())
+}
+
+/// LZH文件名的原始字节，level 2 的文件名和目录名在扩展头中，目录以0xFF分隔
+fn lzh_raw_name(header: &delharc::LhaHeader) -> Vec<u8> {
+    let mut dir = Vec::new();
+    let mut filename = None;
+    for extra in header.iter_extra() {
+        match extra {
+            [LZH_EXT_FILENAME, data @ ..] => filename = Some(data.to_vec()),
+            [LZH_EXT_PATH, data @ ..] => {
+                dir = data
+                    .iter()
+                    .map(|&b| if b == 0xFF { b'/' } else { b })
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+    if !dir.is_empty() && !dir.ends_with(b"/") {
+        dir.push(b'/');
+    }
+    dir.extend(filename.unwrap_or_else(|| header.filename.to_vec()));
+    dir
+}
+
+fn open_lzh(archive_path: &Path) -> Result<delharc::LhaDecodeReader<fs::File>> {
+    delharc::parse_file(archive_path)
+        .with_context(|| format!("无法读取LZH文件: {:?}", archive_path))
+}
+
+fn extract_lzh(archive_path: &Path, dest: &Path, budget: &mut Budget) -> Result<()> {
+    // 先读取所有文件名来判断编码
+    let mut raw_names = Vec::new();
+    let mut reader = open_lzh(archive_path)?;
+    loop {
+        raw_names.push(lzh_raw_name(reader.header()));
+        if !reader
+            .next_file()
+            .map_err(|e| anyhow::anyhow!("读取LZH文件失败: {:?}: {}", archive_path, e))?
+        {
+            break;
+        }
+    }
+    let encoding = detect_encoding(raw_names.iter().map(Vec::as_slice));
+    info!("LZH文件名编码: {}", encoding.name());
+
+    let mut reader = open_lzh(archive_path)?;
+    for raw_name in &raw_names {
+        // Shift_JIS的第二个字节可能是 '\'，需要先解码再按分隔符拆分
+        let name = encoding.decode_without_bom_handling(raw_name).0;
+        if let Some(path) = target_path(dest, &name) {
+            budget.add_file()?;
+            if reader.header().is_directory() {
+                fs::create_dir_all(&path).with_context(|| format!("创建目录失败: {:?}", path))?;
+            } else if !reader.is_decoder_supported() {
+                return Err(anyhow::anyhow!("不支持的LZH压缩方式: {}", name));
+            } else {
+                let mut file = create_file(&path)?;
+                budget
+                    .copy(&mut reader, &mut file)
+                    .with_context(|| format!("解压失败: {}", name))?;
+                reader
+                    .crc_check()
+                    .map_err(|e| anyhow::anyhow!("LZH文件校验失败: {}: {}", name, e))?;
+            }
+        }
+        if !reader
+            .next_file()
+            .map_err(|e| anyhow::anyhow!("读取LZH文件失败: {:?}: {}", archive_path, e))?
+        {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// 解压目录: 压缩包去掉扩展名，例如 "1 - Title.zip" 解压到 "1 - Title/"
+fn extract_dir(archive_path: &Path) -> PathBuf {
+    let name = archive_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stem = name
+        .strip_suffix(".tar.gz")
+        .or_else(|| name.rsplit_once('.').map(|(stem, _)| stem))
+        .unwrap_or(&name);
+    archive_path.with_file_name(stem)
+}
+
+/// 支持解压的压缩格式
+pub fn is_supported(file_type: FileType) -> bool {
+    matches!(
+        file_type,
+        filetype::ZIP | filetype::RAR4 | filetype::RAR5 | filetype::SEVEN_ZIP | filetype::LZH
+    )
+}
+
+/// 选择解压目录，只替换上次解压创建的目录 `previous`
+///
+/// 同名目录已存在但不是解压创建的 (例如手动解压的目录) 时，
+/// 使用 "<目录> (2)"、"<目录> (3)" 等旁边的目录。
+fn choose_dest(archive_path: &Path, previous: Option<&Path>) -> PathBuf {
+    let dest = extract_dir(archive_path);
+    let name = dest
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let chosen = std::iter::once(dest.clone())
+        .chain((2..).map(|n| dest.with_file_name(format!("{} ({})", name, n))))
+        .find(|dir| !dir.exists() || previous == Some(dir.as_path()))
+        .unwrap_or(dest.clone());
+    if chosen != dest {
+        info!("目录已存在且不是解压创建的，解压到: {:?}", chosen);
+    }
+    chosen
+}
+
+/// 把压缩包解压到 `dest` 中，返回文件数和解压后的总大小
+///
+/// 出错时 `dest` 中可能留有部分文件，由调用者删除。
+pub fn extract_to(archive_path: &Path, file_type: FileType, dest: &Path) -> Result<(usize, u64)> {
+    fs::create_dir_all(dest).with_context(|| format!("创建目录失败: {:?}", dest))?;
+    let archive_size = fs::metadata(archive_path)
+        .with_context(|| format!("无法读取文件: {:?}", archive_path))?
+        .len();
+    let mut budget = Budget::new(archive_size);
+    match file_type {
+        filetype::ZIP => extract_zip(archive_path, dest, &mut budget),
+        filetype::RAR4 | filetype::RAR5 => extract_rar(archive_path, dest, &mut budget),
+        filetype::SEVEN_ZIP => extract_7z(archive_path, dest, &mut budget),
+        filetype::LZH => extract_lzh(archive_path, dest, &mut budget),
+        _ => Err(anyhow::anyhow!("不支持解压 {} 文件", file_type.name)),
+    }?;
+    Ok((budget.files, budget.limit - budget.remaining))
+}
+
+/// 把压缩包解压到以压缩包命名的目录中，返回解压的目录
+///
+/// 先解压到 "<目录>.extract.part"，成功后替换上次解压的目录，失败时删除。
+pub fn extract(
+    archive_path: &Path,
+    file_type: FileType,
+    previous: Option<&Path>,
+) -> Result<PathBuf> {
+    let dest = choose_dest(archive_path, previous);
+    let mut temp = dest.as_os_str().to_owned();
+    temp.push(".extract.part");
+    let temp = PathBuf::from(temp);
+    if temp.exists() {
+        fs::remove_dir_all(&temp).with_context(|| format!("删除目录失败: {:?}", temp))?;
+    }
+
+    let (files, bytes) = match extract_to(archive_path, file_type, &temp) {
+        Ok(summary) => summary,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&temp);
+            return Err(e);
+        }
+    };
+
+    if dest.exists() {
+        fs::remove_dir_all(&dest).with_context(|| format!("删除目录失败: {:?}", dest))?;
+    }
+    fs::rename(&temp, &dest)
+        .with_context(|| format!("重命名文件失败: {:?} -> {:?}", temp, dest))?;
+    info!("解压完成: {:?} ({} 个文件, {} 字节)", dest, files, bytes);
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    #[test]
+    fn unsafe_paths() {
+        assert_eq!(safe_relative_path("../x"), None);
+        assert_eq!(safe_relative_path("a/../../x"), None);
+        assert_eq!(safe_relative_path("a\\..\\x"), None);
+        assert_eq!(safe_relative_path("C:\\x"), None);
+        assert_eq!(safe_relative_path("C:/x"), None);
+        assert_eq!(safe_relative_path(""), None);
+        assert_eq!(safe_relative_path("/"), None);
+        assert_eq!(safe_relative_path("./"), None);
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(safe_relative_path("/etc/x"), Some(PathBuf::from("etc/x")));
+        assert_eq!(
+            safe_relative_path("song\\bgm.wav"),
+            Some(PathBuf::from("song/bgm.wav"))
+        );
+        assert_eq!(
+            safe_relative_path("./song//a.bms"),
+            Some(PathBuf::from("song/a.bms"))
+        );
+        assert_eq!(
+            safe_relative_path("so\u{0}ng/a\n.bms\r"),
+            Some(PathBuf::from("song/a.bms"))
+        );
+        assert_eq!(safe_relative_path("\u{1}\u{2}"), None);
+        assert_eq!(
+            safe_relative_path("..a/b.."),
+            Some(PathBuf::from("..a/b.."))
+        );
+    }
+
+    #[test]
+    fn budget_ratio() {
+        let mut budget = Budget::new(10);
+        assert_eq!(budget.limit, 10 * MAX_RATIO);
+        budget.take(600).unwrap();
+        budget.take(400).unwrap();
+        assert!(budget.take(1).is_err());
+
+        let mut budget = Budget::new(10);
+        let mut sink = Vec::new();
+        let data = vec![0u8; (10 * MAX_RATIO) as usize];
+        assert_eq!(budget.copy(&mut data.as_slice(), &mut sink).unwrap(), 1000);
+        let mut budget = Budget::new(10);
+        let data = vec![0u8; (10 * MAX_RATIO + 1) as usize];
+        assert!(budget.copy(&mut data.as_slice(), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn budget_total_size() {
+        let mut budget = Budget::new(MAX_EXTRACTED_SIZE);
+        assert_eq!(budget.limit, MAX_EXTRACTED_SIZE);
+        budget.take(MAX_EXTRACTED_SIZE).unwrap();
+        assert!(budget.take(1).is_err());
+        assert_eq!(Budget::new(u64::MAX).limit, MAX_EXTRACTED_SIZE);
+    }
+
+    #[test]
+    fn budget_files() {
+        let mut budget = Budget::new(1);
+        for _ in 0..MAX_FILES {
+            budget.add_file().unwrap();
+        }
+        assert!(budget.add_file().is_err());
+    }
+
+    #[test]
+    fn shift_jis_names() {
+        // ソ 的第二个字节是 '\'
+        let names: [&[u8]; 2] = [b"\x83\x5C.bms", b"\x83J\x83\x81/\x83\x5C.wav"];
+        let encoding = detect_encoding(names.iter().copied());
+        assert_eq!(encoding, SHIFT_JIS);
+        let name = encoding.decode_without_bom_handling(names[0]).0;
+        assert_eq!(name, "ソ.bms");
+        assert_eq!(safe_relative_path(&name), Some(PathBuf::from("ソ.bms")));
+        let name = encoding.decode_without_bom_handling(names[1]).0;
+        assert_eq!(
+            safe_relative_path(&name),
+            Some(PathBuf::from("カメ/ソ.wav"))
+        );
+    }
+
+    #[test]
+    fn utf8_names() {
+        let names = ["曲/譜面.bms".as_bytes(), b"readme.txt"];
+        assert_eq!(detect_encoding(names.iter().copied()), UTF_8);
+        // ASCII 的文件名也按UTF-8处理
+        assert_eq!(detect_encoding([b"a.bms".as_slice()].into_iter()), UTF_8);
+    }
+
+    #[test]
+    fn zip_slip_skipped() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in [("../evil", "evil"), ("song/a.bms", "#TITLE a")] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let root = std::env::temp_dir().join(format!("downloader-test-{}", std::process::id()));
+        let dest = root.join("out");
+        let archive = root.join("pack.zip");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(&archive, &bytes).unwrap();
+
+        let result = extract_to(&archive, filetype::ZIP, &dest);
+        let evil_written = root.join("evil").exists();
+        let extracted = fs::read_to_string(dest.join("song/a.bms"));
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(result.unwrap().0, 1);
+        assert!(!evil_written);
+        assert_eq!(extracted.unwrap(), "#TITLE a");
+    }
+}
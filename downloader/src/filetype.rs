@@ -0,0 +1,263 @@
+use std::{fs, io::Read, path::Path};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+
+/// 根据文件头识别的文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileType {
+    pub name: &'static str,
+    /// 可用的扩展名，第一个为默认值
+    pub extensions: &'static [&'static str],
+}
+
+pub const ZIP: FileType = FileType {
+    name: "ZIP",
+    extensions: &["zip"],
+};
+pub const RAR4: FileType = FileType {
+    name: "RAR4",
+    extensions: &["rar"],
+};
+pub const RAR5: FileType = FileType {
+    name: "RAR5",
+    extensions: &["rar"],
+};
+pub const SEVEN_ZIP: FileType = FileType {
+    name: "7z",
+    extensions: &["7z"],
+};
+pub const LZH: FileType = FileType {
+    name: "LZH",
+    extensions: &["lzh", "lha"],
+};
+pub const TAR: FileType = FileType {
+    name: "tar",
+    extensions: &["tar"],
+};
+pub const TAR_GZ: FileType = FileType {
+    name: "tar.gz",
+    extensions: &["tar.gz", "tgz"],
+};
+pub const GZIP: FileType = FileType {
+    name: "gzip",
+    extensions: &["gz"],
+};
+pub const EXE: FileType = FileType {
+    name: "EXE",
+    extensions: &["exe"],
+};
+pub const MP3: FileType = FileType {
+    name: "MP3",
+    extensions: &["mp3"],
+};
+pub const OGG: FileType = FileType {
+    name: "Ogg",
+    extensions: &["ogg", "oga"],
+};
+pub const WAV: FileType = FileType {
+    name: "WAV",
+    extensions: &["wav"],
+};
+pub const FLAC: FileType = FileType {
+    name: "FLAC",
+    extensions: &["flac"],
+};
+
+/// 无法识别的文件使用的扩展名
+pub const UNKNOWN_EXTENSION: &str = "bin";
+
+/// tar 的文件头在第257字节处有 "ustar" 标记
+fn is_tar(header: &[u8]) -> bool {
+    header.get(257..262) == Some(b"ustar")
+}
+
+/// LZH 的文件头在第2字节处为压缩方法，例如 "-lh5-"
+fn is_lzh(header: &[u8]) -> bool {
+    match header.get(2..7) {
+        Some([b'-', b'l', kind, method, b'-']) => match kind {
+            b'h' => method.is_ascii_digit() || *method == b'd',
+            b'z' => matches!(method, b's' | b'4' | b'5'),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// MPEG音频帧的同步字 (11位全为1)，并排除保留的版本和层
+fn is_mpeg_frame(header: &[u8]) -> bool {
+    match header {
+        [0xFF, second, ..] => second & 0xE0 == 0xE0 && second & 0x18 != 0x08 && second & 0x06 != 0,
+        _ => false,
+    }
+}
+
+/// 根据文件头识别文件类型，gzip需要解压开头来区分 tar.gz
+pub fn sniff(header: &[u8]) -> Option<FileType> {
+    let file_type = if header.starts_with(b"PK\x03\x04")
+        || header.starts_with(b"PK\x05\x06")
+        || header.starts_with(b"PK\x07\x08")
+    {
+        ZIP
+    } else if header.starts_with(b"Rar!\x1A\x07\x00") {
+        RAR4
+    } else if header.starts_with(b"Rar!\x1A\x07\x01\x00") {
+        RAR5
+    } else if header.starts_with(b"7z\xBC\xAF\x27\x1C") {
+        SEVEN_ZIP
+    } else if is_lzh(header) {
+        LZH
+    } else if header.starts_with(b"\x1F\x8B") {
+        let mut inner = Vec::with_capacity(512);
+        let _ = GzDecoder::new(header).take(512).read_to_end(&mut inner);
+        if is_tar(&inner) { TAR_GZ } else { GZIP }
+    } else if is_tar(header) {
+        TAR
+    } else if header.starts_with(b"MZ") {
+        // 自解压的压缩包
+        EXE
+    } else if header.starts_with(b"OggS") {
+        OGG
+    } else if header.starts_with(b"fLaC") {
+        FLAC
+    } else if header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WAVE") {
+        WAV
+    } else if header.starts_with(b"ID3") || is_mpeg_frame(header) {
+        MP3
+    } else {
+        return None;
+    };
+    Some(file_type)
+}
+
+/// 读取文件开头并识别文件类型
+pub fn sniff_file(path: &Path) -> Result<Option<FileType>> {
+    let file = fs::File::open(path).with_context(|| format!("无法打开文件: {:?}", path))?;
+    let mut header = Vec::with_capacity(4096);
+    file.take(4096)
+        .read_to_end(&mut header)
+        .with_context(|| format!("无法读取文件: {:?}", path))?;
+    Ok(sniff(&header))
+}
+
+/// 文件名的扩展名 (小写)，"x.tar.gz" 为 "tar.gz"
+fn extension_of(name: &str) -> Option<String> {
+    let name = name.to_lowercase();
+    if name.ends_with(".tar.gz") {
+        return Some("tar.gz".to_string());
+    }
+    let (stem, extension) = name.rsplit_once('.')?;
+    let valid = !stem.is_empty()
+        && !extension.is_empty()
+        && extension.len() <= 5
+        && extension.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then(|| extension.to_string())
+}
+
+/// 选择保存文件的扩展名
+///
+/// 以文件头识别的类型为准；站点提供的文件名或URL路径中的扩展名与该类型一致时
+/// 使用其写法 (例如 lha、tgz)。无法识别的文件一律使用 `.bin`，
+/// 不相信文件名中的扩展名 (错误页也可能来自 ".../pack.zip")。
+pub fn choose_extension(file_type: Option<FileType>, names: &[&str]) -> &'static str {
+    let Some(file_type) = file_type else {
+        return UNKNOWN_EXTENSION;
+    };
+    names
+        .iter()
+        .filter_map(|name| extension_of(name))
+        .find_map(|extension| {
+            file_type
+                .extensions
+                .iter()
+                .find(|candidate| **candidate == extension)
+        })
+        .copied()
+        .unwrap_or(file_type.extensions[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::*;
+
+    /// 512字节的tar头，第257字节处为 "ustar"
+    fn tar_header() -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..8].copy_from_slice(b"song.bms");
+        header[257..262].copy_from_slice(b"ustar");
+        header
+    }
+
+    #[test]
+    fn archives() {
+        assert_eq!(sniff(b"PK\x03\x04\x14\x00\x00\x00"), Some(ZIP));
+        assert_eq!(sniff(b"PK\x05\x06\x00\x00"), Some(ZIP));
+        assert_eq!(sniff(b"Rar!\x1A\x07\x00\xCF\x90"), Some(RAR4));
+        assert_eq!(sniff(b"Rar!\x1A\x07\x01\x00\x33\x92"), Some(RAR5));
+        assert_eq!(sniff(b"7z\xBC\xAF\x27\x1C\x00\x04"), Some(SEVEN_ZIP));
+        assert_eq!(sniff(b"\x24\x5A-lh5-\x10\x00"), Some(LZH));
+        assert_eq!(sniff(b"\x24\x5A-lh0-\x10\x00"), Some(LZH));
+        assert_eq!(sniff(b"\x24\x5A-lhx-\x10\x00"), None);
+        assert_eq!(sniff(&tar_header()), Some(TAR));
+        assert_eq!(sniff(b"MZ\x90\x00"), Some(EXE));
+    }
+
+    #[test]
+    fn gzip_and_tar_gz() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tar_header()).unwrap();
+        assert_eq!(sniff(&encoder.finish().unwrap()), Some(TAR_GZ));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"#TITLE song").unwrap();
+        assert_eq!(sniff(&encoder.finish().unwrap()), Some(GZIP));
+    }
+
+    #[test]
+    fn audio() {
+        assert_eq!(sniff(b"ID3\x03\x00\x00\x00"), Some(MP3));
+        assert_eq!(sniff(b"\xFF\xFB\x90\x64"), Some(MP3));
+        // 保留的MPEG版本
+        assert_eq!(sniff(b"\xFF\xEB\x90\x64"), None);
+        assert_eq!(sniff(b"OggS\x00\x02"), Some(OGG));
+        assert_eq!(sniff(b"fLaC\x00\x00"), Some(FLAC));
+        assert_eq!(sniff(b"RIFF\x24\x00\x00\x00WAVEfmt "), Some(WAV));
+        assert_eq!(sniff(b"RIFF\x24\x00\x00\x00AVI "), None);
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(sniff(b""), None);
+        assert_eq!(sniff(b"<!DOCTYPE html><html>"), None);
+        assert_eq!(sniff(b"#TITLE song"), None);
+    }
+
+    #[test]
+    fn extension_from_names() {
+        assert_eq!(choose_extension(Some(ZIP), &["pack.zip"]), "zip");
+        assert_eq!(choose_extension(Some(LZH), &["pack.LHA"]), "lha");
+        assert_eq!(
+            choose_extension(Some(LZH), &["pack.zip", "pack.lzh"]),
+            "lzh"
+        );
+        assert_eq!(choose_extension(Some(TAR_GZ), &["pack.tar.gz"]), "tar.gz");
+        assert_eq!(choose_extension(Some(TAR_GZ), &["pack.tgz"]), "tgz");
+        // 文件名与文件头不一致时以文件头为准
+        assert_eq!(choose_extension(Some(RAR5), &["pack.zip"]), "rar");
+        assert_eq!(choose_extension(Some(SEVEN_ZIP), &["download.php"]), "7z");
+        assert_eq!(choose_extension(Some(ZIP), &[]), "zip");
+    }
+
+    #[test]
+    fn unknown_type_is_bin() {
+        // 来自 ".../pack.zip" 的HTML错误页
+        let html = sniff(b"<!DOCTYPE html><html><body>Not Found</body></html>");
+        assert_eq!(choose_extension(html, &["pack.zip"]), "bin");
+        assert_eq!(choose_extension(None, &["pack.bms", "readme.txt"]), "bin");
+        assert_eq!(choose_extension(None, &[]), "bin");
+    }
+}
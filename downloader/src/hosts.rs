@@ -0,0 +1,413 @@
+use std::{future::Future, io::Write, pin::Pin, sync::LazyLock, time::Duration};
+
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use reqwest::{
+    Client, RequestBuilder, StatusCode,
+    header::{
+        CONTENT_DISPOSITION, CONTENT_RANGE, ETAG, HeaderName, IF_RANGE, LAST_MODIFIED, RANGE,
+        RETRY_AFTER,
+    },
+};
+use url::Url;
+
+use crate::{
+    disposition,
+    retry::{self, DownloadError},
+};
+
+mod direct;
+mod dropbox;
+mod google_drive;
+mod mediafire;
+mod onedrive;
+
+pub use direct::DirectLink;
+pub use dropbox::DropboxLink;
+pub use google_drive::GoogleDriveLink;
+pub use mediafire::MediaFireLink;
+pub use onedrive::OneDriveLink;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// 部分网盘会拒绝默认的User-Agent
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36";
+
+/// 上传站点的默认优先级
+const SITE_PRIORITY: u8 = 10;
+
+/// 每个上传站点一个链接类型，在各自的模块中实现并在 `LINK_RESOLVERS` 中注册
+pub trait LinkTypeTrait: std::fmt::Debug + Send + Sync {
+    fn is_downloadable(&self) -> bool;
+    fn get_direct_url(&self) -> Option<String>;
+    fn get_url(&self) -> &str;
+    fn get_type_name(&self) -> &'static str;
+
+    /// 匹配优先级，数值越小越先尝试
+    fn priority() -> u8
+    where
+        Self: Sized;
+    /// 处理的主机名，同时匹配其子域名；为空时匹配所有主机
+    fn hosts() -> &'static [&'static str]
+    where
+        Self: Sized;
+    /// 处理的路径前缀，为空时匹配所有路径
+    fn paths() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+    /// 从主机和路径已经匹配的URL创建链接
+    fn from_url(url: &Url) -> Option<Self>
+    where
+        Self: Sized;
+
+    /// 没有其他链接类型匹配时使用的通用类型，其站点需要按主机名判断
+    fn is_fallback(&self) -> bool {
+        false
+    }
+
+    /// 同一站点同时进行的下载数上限
+    fn max_concurrent(&self) -> usize {
+        4
+    }
+
+    /// 解析出实际的下载地址并发起请求，默认直接请求 `get_direct_url`
+    ///
+    /// 续传时请求文件的那一次带上 `resume` 的Range，网盘页面等请求不带。
+    fn resolve<'a>(
+        &'a self,
+        client: &'a Client,
+        resume: Option<&'a Resume>,
+    ) -> BoxFuture<'a, Result<Download>> {
+        Box::pin(async move {
+            let url = self
+                .get_direct_url()
+                .ok_or_else(|| anyhow::anyhow!("无法获取下载链接: {}", self.get_url()))?;
+            Download::get(client, &url, resume).await
+        })
+    }
+}
+
+pub type LinkType = Box<dyn LinkTypeTrait>;
+
+/// 已经开始的下载：响应体和文件信息
+#[derive(Debug)]
+pub struct Download {
+    /// 最终的下载地址
+    pub url: String,
+    /// 站点提供的文件名 (Content-Disposition 或网盘页面中的文件名)，用来选择扩展名
+    pub filename: Option<String>,
+    /// 完整文件的大小
+    pub content_length: Option<u64>,
+    /// 响应体在文件中的起始位置，断点续传时不为0
+    pub offset: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    response: reqwest::Response,
+}
+
+/// 续传已下载的部分：从 offset 处请求，If-Range 保证文件没有变化时才返回部分内容
+#[derive(Debug, Clone)]
+pub struct Resume {
+    pub offset: u64,
+    /// 开始下载时的强ETag或Last-Modified
+    pub validator: String,
+}
+
+impl Resume {
+    fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+            .header(RANGE, format!("bytes={}-", self.offset))
+            .header(IF_RANGE, &self.validator)
+    }
+}
+
+fn header_value(response: &reqwest::Response, name: HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// 非2xx的响应按状态码分为可以重试和不能重试的错误
+pub fn check_status(response: &reqwest::Response) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let retry_after =
+        header_value(response, RETRY_AFTER).and_then(|value| retry::parse_retry_after(&value));
+    Err(DownloadError::from_status(response.url().as_str(), status, retry_after).into())
+}
+
+/// If-Range 只能使用强ETag，弱ETag (W/"...") 不能说明内容逐字节相同
+pub fn strong_etag(etag: &Option<String>) -> Option<&str> {
+    etag.as_deref().filter(|etag| !etag.starts_with("W/"))
+}
+
+/// 解析 Content-Range: bytes START-END/TOTAL，返回起始位置和完整大小
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _) = span.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+/// 请求文件，`resume` 不为空时带上Range和If-Range
+pub fn file_request(client: &Client, url: &str, resume: Option<&Resume>) -> RequestBuilder {
+    let request = client.get(url);
+    match resume {
+        Some(resume) => resume.apply(request),
+        None => request,
+    }
+}
+
+impl Download {
+    pub async fn get(client: &Client, url: &str, resume: Option<&Resume>) -> Result<Self> {
+        let response = file_request(client, url, resume)
+            .send()
+            .await
+            .with_context(|| format!("HTTP请求失败: {}", url))?;
+        Self::open(client, response, resume).await
+    }
+
+    /// 续传请求的响应：得到请求的范围时从 offset 处继续，否则为完整文件
+    ///
+    /// 文件在服务器上变化或服务器忽略Range时返回完整文件 (offset 为0)；
+    /// 返回的范围不对时重新请求完整文件。
+    pub async fn open(
+        client: &Client,
+        response: reqwest::Response,
+        resume: Option<&Resume>,
+    ) -> Result<Self> {
+        let Some(resume) = resume else {
+            return Self::from_response(response);
+        };
+        let status = response.status();
+        let content_range = header_value(&response, CONTENT_RANGE);
+        match content_range.as_deref().and_then(parse_content_range) {
+            Some((start, total))
+                if status == StatusCode::PARTIAL_CONTENT && start == resume.offset =>
+            {
+                let mut download = Self::from_response(response)?;
+                download.offset = resume.offset;
+                download.content_length =
+                    total.or(download.content_length.map(|len| len + resume.offset));
+                Ok(download)
+            }
+            _ if status == StatusCode::PARTIAL_CONTENT
+                || status == StatusCode::RANGE_NOT_SATISFIABLE =>
+            {
+                let url = response.url().to_string();
+                drop(response);
+                let response = client
+                    .get(&url)
+                    .send()
+                    .await
+                    .with_context(|| format!("HTTP请求失败: {}", url))?;
+                Self::from_response(response)
+            }
+            _ => Self::from_response(response),
+        }
+    }
+
+    /// 非2xx的响应视为失败
+    pub fn from_response(response: reqwest::Response) -> Result<Self> {
+        check_status(&response)?;
+        let url = response.url().to_string();
+        let filename = response
+            .headers()
+            .get(CONTENT_DISPOSITION)
+            .and_then(|value| disposition::parse_filename(value.as_bytes()));
+        Ok(Download {
+            url,
+            filename,
+            content_length: response.content_length(),
+            offset: 0,
+            etag: header_value(&response, ETAG),
+            last_modified: header_value(&response, LAST_MODIFIED),
+            response,
+        })
+    }
+
+    /// 响应中没有文件名时使用站点页面中的文件名
+    pub fn or_filename(mut self, filename: Option<String>) -> Self {
+        if self.filename.is_none() {
+            self.filename = filename;
+        }
+        self
+    }
+
+    /// 把响应体逐块写入并更新进度条，返回写入的字节数
+    pub async fn write_to(
+        &mut self,
+        writer: &mut (dyn Write + Send),
+        bar: &ProgressBar,
+    ) -> Result<u64> {
+        let mut written = 0u64;
+        while let Some(chunk) = self
+            .response
+            .chunk()
+            .await
+            .with_context(|| format!("读取响应失败: {}", self.url))?
+        {
+            writer.write_all(&chunk).context("写入文件失败")?;
+            written += chunk.len() as u64;
+            bar.inc(chunk.len() as u64);
+        }
+        // 连接提前关闭时响应体可能不完整
+        if let Some(expected) = self.content_length
+            && self.offset + written < expected
+        {
+            return Err(DownloadError::Transient {
+                message: format!(
+                    "下载不完整: {} ({}/{} 字节)",
+                    self.url,
+                    self.offset + written,
+                    expected
+                ),
+                retry_after: None,
+            }
+            .into());
+        }
+        Ok(written)
+    }
+}
+
+pub fn http_client() -> Result<Client> {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .redirect(reqwest::redirect::Policy::limited(10))
+        .connect_timeout(Duration::from_secs(30))
+        .read_timeout(Duration::from_secs(60))
+        .build()
+        .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))
+}
+
+pub fn host_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+/// 注册表中的一个链接类型
+struct LinkResolver {
+    priority: u8,
+    parse: fn(&Url) -> Option<LinkType>,
+}
+
+fn parse_link<T: LinkTypeTrait + 'static>(url: &Url) -> Option<LinkType> {
+    let host = url.host_str()?;
+    let host_ok = T::hosts().is_empty() || T::hosts().iter().any(|d| host_matches(host, d));
+    let path_ok = T::paths().is_empty() || T::paths().iter().any(|p| url.path().starts_with(p));
+    if !(host_ok && path_ok) {
+        return None;
+    }
+    T::from_url(url).map(|link| Box::new(link) as LinkType)
+}
+
+fn resolver<T: LinkTypeTrait + 'static>() -> LinkResolver {
+    LinkResolver {
+        priority: T::priority(),
+        parse: parse_link::<T>,
+    }
+}
+
+/// 所有链接类型按优先级排列，新增上传站点时在这里注册
+static LINK_RESOLVERS: LazyLock<Vec<LinkResolver>> = LazyLock::new(|| {
+    let mut resolvers = vec![
+        resolver::<GoogleDriveLink>(),
+        resolver::<DropboxLink>(),
+        resolver::<OneDriveLink>(),
+        resolver::<MediaFireLink>(),
+        resolver::<DirectLink>(),
+    ];
+    resolvers.sort_by_key(|resolver| resolver.priority);
+    resolvers
+});
+
+pub fn create_link_from_url(url: &Url) -> LinkType {
+    LINK_RESOLVERS
+        .iter()
+        .find_map(|resolver| (resolver.parse)(url))
+        .unwrap_or_else(|| {
+            Box::new(DirectLink {
+                url: url.to_string(),
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+
+    fn link(url: &str) -> LinkType {
+        create_link_from_url(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn resolvers_sorted_by_priority() {
+        let priorities: Vec<u8> = LINK_RESOLVERS.iter().map(|r| r.priority).collect();
+        assert!(priorities.is_sorted());
+        // 直链匹配所有地址，必须在最后
+        assert_eq!(priorities.last(), Some(&u8::MAX));
+        assert_eq!(priorities.iter().filter(|&&p| p == u8::MAX).count(), 1);
+    }
+
+    #[test]
+    fn link_types_by_host() {
+        let cases = [
+            ("https://drive.google.com/file/d/1AbC/view", "GoogleDrive"),
+            (
+                "https://drive.usercontent.google.com/download?id=1AbC",
+                "GoogleDrive",
+            ),
+            ("https://www.dropbox.com/s/abc/pack.zip?dl=0", "Dropbox"),
+            (
+                "https://dl.dropboxusercontent.com/scl/fi/abc/pack.zip",
+                "Dropbox",
+            ),
+            ("https://1drv.ms/u/s!AbC", "OneDrive"),
+            ("https://onedrive.live.com/redir?resid=AbC", "OneDrive"),
+            (
+                "https://www.mediafire.com/file/abc/pack.zip/file",
+                "MediaFire",
+            ),
+            ("https://example.com/pack.zip", "Direct"),
+        ];
+        for (url, type_name) in cases {
+            let link = link(url);
+            assert_eq!(link.get_type_name(), type_name, "{}", url);
+            assert_eq!(link.is_fallback(), type_name == "Direct", "{}", url);
+        }
+    }
+
+    #[test]
+    fn paths_and_subdomains() {
+        // 文件服务器上的地址不是下载页面，按直链处理
+        assert_eq!(
+            link("https://download2390.mediafire.com/xyz/pack.zip").get_type_name(),
+            "Direct"
+        );
+        assert_eq!(
+            link("https://notdropbox.com/s/abc/pack.zip").get_type_name(),
+            "Direct"
+        );
+        assert!(host_matches("www.dropbox.com", "dropbox.com"));
+        assert!(host_matches("dropbox.com", "dropbox.com"));
+        assert!(!host_matches("notdropbox.com", "dropbox.com"));
+    }
+
+    #[test]
+    fn content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/200"),
+            Some((100, Some(200)))
+        );
+        assert_eq!(parse_content_range("bytes 100-199/*"), Some((100, None)));
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+    }
+}
@@ -0,0 +1,38 @@
+use url::Url;
+
+use super::LinkTypeTrait;
+
+#[derive(Debug, Clone)]
+pub struct DirectLink {
+    pub url: String,
+}
+
+impl LinkTypeTrait for DirectLink {
+    fn is_downloadable(&self) -> bool {
+        true
+    }
+    fn get_direct_url(&self) -> Option<String> {
+        Some(self.url.clone())
+    }
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+    fn get_type_name(&self) -> &'static str {
+        "Direct"
+    }
+    // 直链匹配所有地址，必须最后尝试
+    fn priority() -> u8 {
+        u8::MAX
+    }
+    fn hosts() -> &'static [&'static str] {
+        &[]
+    }
+    fn is_fallback(&self) -> bool {
+        true
+    }
+    fn from_url(url: &Url) -> Option<Self> {
+        Some(DirectLink {
+            url: url.to_string(),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use url::Url;
+
+use super::{LinkTypeTrait, SITE_PRIORITY};
+
+#[derive(Debug, Clone)]
+pub struct DropboxLink {
+    pub url: String,
+}
+
+impl LinkTypeTrait for DropboxLink {
+    fn is_downloadable(&self) -> bool {
+        true
+    }
+    fn get_direct_url(&self) -> Option<String> {
+        // 把 dl=0 改为 dl=1，保留 rlkey 等其他参数
+        let mut url = Url::parse(&self.url).ok()?;
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| key != "dl")
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair("dl", "1");
+        Some(url.to_string())
+    }
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+    fn get_type_name(&self) -> &'static str {
+        "Dropbox"
+    }
+    fn priority() -> u8 {
+        SITE_PRIORITY
+    }
+    fn hosts() -> &'static [&'static str] {
+        &["dropbox.com", "dropboxusercontent.com"]
+    }
+    fn from_url(url: &Url) -> Option<Self> {
+        Some(DropboxLink {
+            url: url.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn direct_url(url: &str) -> Option<String> {
+        DropboxLink { url: url.into() }.get_direct_url()
+    }
+
+    #[test]
+    fn dl_parameter() {
+        assert_eq!(
+            direct_url("https://www.dropbox.com/s/abc/pack.zip?dl=0"),
+            Some("https://www.dropbox.com/s/abc/pack.zip?dl=1".into())
+        );
+        assert_eq!(
+            direct_url("https://www.dropbox.com/scl/fi/abc/pack.zip?rlkey=xyz&dl=0"),
+            Some("https://www.dropbox.com/scl/fi/abc/pack.zip?rlkey=xyz&dl=1".into())
+        );
+        assert_eq!(
+            direct_url("https://www.dropbox.com/s/abc/pack.zip"),
+            Some("https://www.dropbox.com/s/abc/pack.zip?dl=1".into())
+        );
+    }
+}
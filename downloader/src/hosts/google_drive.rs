@@ -0,0 +1,378 @@
+use anyhow::{Context, Result};
+use log::info;
+use reqwest::Client;
+use url::Url;
+
+use super::{
+    BoxFuture, Download, LinkTypeTrait, Resume, SITE_PRIORITY, check_status, file_request,
+};
+use crate::retry::DownloadError;
+
+#[derive(Debug, Clone)]
+pub struct GoogleDriveLink {
+    pub share_id: String,
+}
+
+impl LinkTypeTrait for GoogleDriveLink {
+    fn is_downloadable(&self) -> bool {
+        true
+    }
+    fn get_direct_url(&self) -> Option<String> {
+        if self.share_id.starts_with("https://") {
+            Some(self.share_id.clone())
+        } else {
+            Some(format!(
+                "https://drive.google.com/uc?export=download&id={}",
+                self.share_id
+            ))
+        }
+    }
+    fn get_url(&self) -> &str {
+        &self.share_id
+    }
+    fn get_type_name(&self) -> &'static str {
+        "GoogleDrive"
+    }
+    fn priority() -> u8 {
+        SITE_PRIORITY
+    }
+    fn hosts() -> &'static [&'static str] {
+        &["drive.google.com", "drive.usercontent.google.com"]
+    }
+    fn from_url(url: &Url) -> Option<Self> {
+        // 格式: /file/d/ID/view 或 /uc?id=ID, /download?id=ID
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(Iterator::collect)
+            .unwrap_or_default();
+        let id = segments
+            .windows(3)
+            .find(|w| w[0] == "file" && w[1] == "d" && !w[2].is_empty())
+            .map(|w| w[2].to_string())
+            .or_else(|| {
+                url.query_pairs()
+                    .find(|(key, _)| key == "id")
+                    .map(|(_, value)| value.into_owned())
+            });
+        Some(GoogleDriveLink {
+            share_id: id.unwrap_or_else(|| url.to_string()),
+        })
+    }
+
+    // 同时下载多个文件很容易触发Google Drive的限制
+    fn max_concurrent(&self) -> usize {
+        1
+    }
+
+    fn resolve<'a>(
+        &'a self,
+        client: &'a Client,
+        resume: Option<&'a Resume>,
+    ) -> BoxFuture<'a, Result<Download>> {
+        Box::pin(async move {
+            let file_id = if self.share_id.starts_with("https://") {
+                extract_google_drive_id_from_url(&self.share_id).ok_or_else(|| {
+                    anyhow::anyhow!("无法从Google Drive URL中提取文件ID: {}", self.share_id)
+                })?
+            } else {
+                self.share_id.clone()
+            };
+            resolve_file(client, &file_id, resume).await
+        })
+    }
+}
+
+/// 大文件会先返回病毒扫描的确认页面，需要从页面中取得实际的下载地址
+///
+/// 小文件的第一个请求直接返回文件，所以两个请求都带上续传的Range；
+/// 确认页面的ETag与文件不同，If-Range 不匹配时服务器返回完整的页面。
+async fn resolve_file(client: &Client, file_id: &str, resume: Option<&Resume>) -> Result<Download> {
+    let confirm_url = format!("https://drive.google.com/uc?export=download&id={}", file_id);
+    info!("第一步：获取确认页面，URL: {}", confirm_url);
+
+    let confirm_response = file_request(client, &confirm_url, resume)
+        .send()
+        .await
+        .with_context(|| format!("获取确认页面失败: {}", confirm_url))?;
+
+    check_status(&confirm_response)?;
+
+    // 小文件没有确认页面，直接返回文件内容
+    let is_html = confirm_response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if !is_html {
+        return Download::open(client, confirm_response, resume).await;
+    }
+
+    let confirm_content = confirm_response
+        .text()
+        .await
+        .with_context(|| format!("读取确认页面失败: {}", confirm_url))?;
+    info!("确认页面长度: {} 字节", confirm_content.len());
+
+    // 下载次数过多时返回配额用尽的页面，当天内重试也不会成功
+    if is_quota_exceeded(&confirm_content) {
+        return Err(DownloadError::Permanent(format!(
+            "Google Drive文件的下载配额已用完: {}",
+            file_id
+        ))
+        .into());
+    }
+
+    // 从HTML内容中提取下载URL和文件名
+    let download_url = if let Some(url) = extract_download_url_from_html(&confirm_content) {
+        info!("从HTML中提取到下载URL: {}", url);
+        url
+    } else {
+        // 如果没有找到完整的下载URL，尝试使用确认token
+        let confirm_token = extract_confirm_token_from_html(&confirm_content)?;
+        info!("提取到的确认token: '{}'", confirm_token);
+
+        if confirm_token.is_empty() {
+            info!("没有找到确认token，直接使用原始URL");
+            confirm_url.clone()
+        } else {
+            let url = format!(
+                "https://drive.google.com/uc?export=download&confirm={}&id={}",
+                confirm_token, file_id
+            );
+            info!("使用确认token构造下载URL: {}", url);
+            url
+        }
+    };
+
+    // 尝试从HTML中提取原始文件名
+    let original_filename = extract_filename_from_html(&confirm_content);
+
+    info!("第二步：下载文件，URL: {}", download_url);
+    let response = file_request(client, &download_url, resume)
+        .send()
+        .await
+        .with_context(|| format!("下载文件失败: {}", download_url))?;
+    info!("HTTP响应状态: {}", response.status());
+
+    // 优先使用Content-Disposition中的文件名，然后是HTML中的文件名
+    Ok(Download::open(client, response, resume)
+        .await?
+        .or_filename(original_filename))
+}
+
+fn is_quota_exceeded(html_content: &str) -> bool {
+    let lower = html_content.to_lowercase();
+    lower.contains("quota exceeded")
+        || lower.contains("too many users have viewed or downloaded this file")
+}
+
+pub fn extract_confirm_token_from_html(html_content: &str) -> Result<String> {
+    // 在HTML中查找确认token
+    // 通常格式为: <a href="/uc?export=download&confirm=TOKEN&id=FILE_ID"
+    if let Some(start) = html_content.find("confirm=") {
+        let token_start = start + 8;
+        if let Some(end) = html_content[token_start..].find('&') {
+            return Ok(html_content[token_start..token_start + end].to_string());
+        } else if let Some(end) = html_content[token_start..].find('"') {
+            return Ok(html_content[token_start..token_start + end].to_string());
+        } else {
+            return Ok(html_content[token_start..].to_string());
+        }
+    }
+
+    // 也尝试查找其他可能的格式
+    if let Some(start) = html_content.find("&confirm=") {
+        let token_start = start + 9;
+        if let Some(end) = html_content[token_start..].find('&') {
+            return Ok(html_content[token_start..token_start + end].to_string());
+        } else if let Some(end) = html_content[token_start..].find('"') {
+            return Ok(html_content[token_start..token_start + end].to_string());
+        } else {
+            return Ok(html_content[token_start..].to_string());
+        }
+    }
+
+    Ok(String::new())
+}
+
+/// 表单中隐藏字段的值: name="NAME" value="VALUE"
+fn hidden_input_value(html_content: &str, name: &str) -> Option<String> {
+    let pattern = format!("name=\"{}\" value=\"", name);
+    let value_start = html_content.find(&pattern)? + pattern.len();
+    let end = html_content[value_start..].find('"')?;
+    Some(html_content[value_start..value_start + end].to_string())
+}
+
+pub fn extract_download_url_from_html(html_content: &str) -> Option<String> {
+    // 病毒扫描确认页面的表单中有 id、export、confirm、uuid 四个隐藏字段
+    let id = hidden_input_value(html_content, "id");
+    let export = hidden_input_value(html_content, "export");
+    let confirm = hidden_input_value(html_content, "confirm");
+    let uuid = hidden_input_value(html_content, "uuid");
+
+    info!(
+        "提取到的参数: id={:?}, export={:?}, confirm={:?}, uuid={:?}",
+        id, export, confirm, uuid
+    );
+
+    // 构造完整的下载URL
+    if let (Some(id_val), Some(export_val), Some(confirm_val), Some(uuid_val)) =
+        (id, export, confirm, uuid)
+    {
+        let url = format!(
+            "https://drive.usercontent.google.com/download?id={}&export={}&confirm={}&uuid={}",
+            id_val, export_val, confirm_val, uuid_val
+        );
+        info!("构造的下载URL: {}", url);
+        return Some(url);
+    }
+
+    None
+}
+
+pub fn extract_filename_from_html(html_content: &str) -> Option<String> {
+    // 从HTML中提取文件名，格式如: <a href="/open?id=...">filename.zip</a>
+    if let Some(start) = html_content.find(">")
+        && let Some(end) = html_content[start + 1..].find("<")
+    {
+        let filename = &html_content[start + 1..start + 1 + end];
+        if filename.contains('.') && !filename.contains(' ') {
+            info!("从HTML中提取到文件名: {}", filename);
+            return Some(filename.to_string());
+        }
+    }
+
+    None
+}
+
+pub fn extract_google_drive_id_from_url(url: &str) -> Option<String> {
+    // 匹配格式: https://drive.google.com/file/d/ID/view
+    if let Some(start) = url.find("/file/d/") {
+        let id_start = start + 8;
+        if let Some(end) = url[id_start..].find("/") {
+            return Some(url[id_start..id_start + end].to_string());
+        }
+    }
+
+    // 匹配格式: https://drive.google.com/uc?id=ID 或 https://drive.google.com/uc?export=download&id=ID
+    if let Some(start) = url.find("?id=") {
+        let id_start = start + 4;
+        if let Some(end) = url[id_start..].find("&") {
+            return Some(url[id_start..id_start + end].to_string());
+        } else {
+            return Some(url[id_start..].to_string());
+        }
+    }
+
+    // 匹配格式: https://drive.google.com/uc?export=download&id=ID
+    if let Some(start) = url.find("&id=") {
+        let id_start = start + 4;
+        if let Some(end) = url[id_start..].find("&") {
+            return Some(url[id_start..id_start + end].to_string());
+        } else {
+            return Some(url[id_start..].to_string());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+
+    fn from_url(url: &str) -> GoogleDriveLink {
+        GoogleDriveLink::from_url(&Url::parse(url).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn share_ids() {
+        assert_eq!(
+            from_url("https://drive.google.com/file/d/1AbC-d_E/view?usp=sharing").share_id,
+            "1AbC-d_E"
+        );
+        assert_eq!(
+            from_url("https://drive.google.com/uc?export=download&id=1AbC").share_id,
+            "1AbC"
+        );
+        assert_eq!(
+            from_url("https://drive.usercontent.google.com/u/0/uc?id=1AbC&export=download")
+                .share_id,
+            "1AbC"
+        );
+        assert_eq!(
+            from_url("https://drive.google.com/drive/folders/xyz").share_id,
+            "https://drive.google.com/drive/folders/xyz"
+        );
+        assert_eq!(
+            from_url("https://drive.google.com/file/d/1AbC/view").get_direct_url(),
+            Some("https://drive.google.com/uc?export=download&id=1AbC".into())
+        );
+    }
+
+    #[test]
+    fn ids_from_url_strings() {
+        assert_eq!(
+            extract_google_drive_id_from_url("https://drive.google.com/file/d/1AbC/view"),
+            Some("1AbC".into())
+        );
+        assert_eq!(
+            extract_google_drive_id_from_url("https://drive.google.com/uc?id=1AbC&export=download"),
+            Some("1AbC".into())
+        );
+        assert_eq!(
+            extract_google_drive_id_from_url("https://drive.google.com/uc?export=download&id=1AbC"),
+            Some("1AbC".into())
+        );
+        assert_eq!(
+            extract_google_drive_id_from_url("https://example.com/"),
+            None
+        );
+    }
+
+    #[test]
+    fn download_url_from_confirm_form() {
+        let html = r#"<form id="download-form" action="https://drive.usercontent.google.com/download" method="get">
+            <input type="submit" id="uc-download-link" value="Download anyway"/>
+            <input type="hidden" name="id" value="1AbC">
+            <input type="hidden" name="export" value="download">
+            <input type="hidden" name="confirm" value="t">
+            <input type="hidden" name="uuid" value="0f1e2d3c">
+            </form>"#;
+        assert_eq!(
+            extract_download_url_from_html(html),
+            Some(
+                "https://drive.usercontent.google.com/download?id=1AbC&export=download&confirm=t&uuid=0f1e2d3c"
+                    .into()
+            )
+        );
+        let without_uuid = html.replace(r#"name="uuid""#, r#"name="other""#);
+        assert_eq!(extract_download_url_from_html(&without_uuid), None);
+    }
+
+    #[test]
+    fn confirm_tokens() {
+        let html = r#"<a href="/uc?export=download&amp;confirm=AbC1&amp;id=1AbC">Download</a>"#;
+        assert_eq!(extract_confirm_token_from_html(html).unwrap(), "AbC1");
+        assert_eq!(
+            extract_confirm_token_from_html(r#"<a href="/uc?confirm=t">"#).unwrap(),
+            "t"
+        );
+        assert_eq!(
+            extract_confirm_token_from_html("<html></html>").unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn quota_pages() {
+        assert!(is_quota_exceeded(
+            "<p>Too many users have viewed or downloaded this file recently.</p>"
+        ));
+        assert!(!is_quota_exceeded(
+            "<p>Google Drive can't scan this file for viruses.</p>"
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use url::Url;
+
+use super::{
+    BoxFuture, Download, LinkTypeTrait, Resume, SITE_PRIORITY, check_status, host_matches,
+};
+use crate::retry::DownloadError;
+
+#[derive(Debug, Clone)]
+pub struct MediaFireLink {
+    pub url: String,
+}
+
+impl LinkTypeTrait for MediaFireLink {
+    fn is_downloadable(&self) -> bool {
+        true
+    }
+    fn get_direct_url(&self) -> Option<String> {
+        Some(self.url.clone())
+    }
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+    fn get_type_name(&self) -> &'static str {
+        "MediaFire"
+    }
+    fn priority() -> u8 {
+        SITE_PRIORITY
+    }
+    fn hosts() -> &'static [&'static str] {
+        &["mediafire.com"]
+    }
+    // 只匹配下载页面，downloadXXXX.mediafire.com 上的文件地址作为直链下载
+    fn paths() -> &'static [&'static str] {
+        &["/file/", "/file_premium/", "/download/", "/view/"]
+    }
+    fn from_url(url: &Url) -> Option<Self> {
+        Some(MediaFireLink {
+            url: url.to_string(),
+        })
+    }
+
+    // 下载页面中的按钮指向实际的文件地址
+    fn resolve<'a>(
+        &'a self,
+        client: &'a Client,
+        resume: Option<&'a Resume>,
+    ) -> BoxFuture<'a, Result<Download>> {
+        Box::pin(async move {
+            let response = client
+                .get(&self.url)
+                .send()
+                .await
+                .with_context(|| format!("访问MediaFire链接失败: {}", self.url))?;
+
+            check_status(&response)?;
+            // 已删除的文件会重定向到 error.php
+            if response.url().path().contains("error.php") {
+                return Err(DownloadError::Permanent(format!(
+                    "MediaFire上的文件已被删除: {}",
+                    self.url
+                ))
+                .into());
+            }
+
+            let html_content = response
+                .text()
+                .await
+                .with_context(|| format!("读取MediaFire页面失败: {}", self.url))?;
+
+            let download_url = extract_mediafire_download_url(&html_content).ok_or_else(|| {
+                anyhow::anyhow!("无法从MediaFire页面中提取下载链接: {}", self.url)
+            })?;
+
+            Download::get(client, &download_url, resume).await
+        })
+    }
+}
+
+/// 下载页面中指向文件服务器 (downloadXXXX.mediafire.com) 的链接
+///
+/// 现在的页面为 `<a ... href="..." id="downloadButton">`，旧页面的按钮在
+/// `download_link` 中，两者都指向文件服务器。
+pub fn extract_mediafire_download_url(html_content: &str) -> Option<String> {
+    html_content
+        .split("href=\"")
+        .skip(1)
+        .filter_map(|rest| rest.split('"').next())
+        .find(|href| {
+            Url::parse(href).ok().is_some_and(|url| {
+                url.host_str().is_some_and(|host| {
+                    host.starts_with("download") && host_matches(host, "mediafire.com")
+                })
+            })
+        })
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract_mediafire_download_url;
+
+    #[test]
+    fn download_button() {
+        let html = r#"<link rel="canonical" href="https://www.mediafire.com/file/abc123/pack.zip/file">
+            <a href="https://www.mediafire.com/download_repair.php">Repair</a>
+            <a class="input popsok" aria-label="Download file"
+               href="https://download2390.mediafire.com/xyz/abc123/pack.zip" id="downloadButton">"#;
+        assert_eq!(
+            extract_mediafire_download_url(html),
+            Some("https://download2390.mediafire.com/xyz/abc123/pack.zip".into())
+        );
+    }
+
+    #[test]
+    fn old_download_link() {
+        let html = r#"<div class="download_link"><a href="http://download1.mediafire.com/abc/pack.rar">Download</a></div>"#;
+        assert_eq!(
+            extract_mediafire_download_url(html),
+            Some("http://download1.mediafire.com/abc/pack.rar".into())
+        );
+    }
+
+    #[test]
+    fn no_download_link() {
+        let html = r#"<a href="https://www.mediafire.com/download_repair.php">Repair</a>
+            <a href="https://notmediafire.com/download/x.zip">x</a>"#;
+        assert_eq!(extract_mediafire_download_url(html), None);
+        assert_eq!(extract_mediafire_download_url(""), None);
+    }
+}
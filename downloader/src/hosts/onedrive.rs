@@ -0,0 +1,84 @@
+use url::Url;
+
+use super::{LinkTypeTrait, SITE_PRIORITY, host_matches};
+
+#[derive(Debug, Clone)]
+pub struct OneDriveLink {
+    pub url: String,
+}
+
+/// onedrive.live.com 的 /redir 和 /embed 链接改为 /download 即为文件本身
+///
+/// 1drv.ms 短链接和 /?id= 等地址打开的是网页版的预览页面，下载到的是HTML，
+/// 无法直接下载。
+fn direct_url(url: &str) -> Option<String> {
+    let mut url = Url::parse(url).ok()?;
+    if !url
+        .host_str()
+        .is_some_and(|host| host_matches(host, "onedrive.live.com"))
+    {
+        return None;
+    }
+    match url.path() {
+        "/download" => {}
+        "/redir" | "/embed" => url.set_path("/download"),
+        _ => return None,
+    }
+    Some(url.to_string())
+}
+
+impl LinkTypeTrait for OneDriveLink {
+    fn is_downloadable(&self) -> bool {
+        direct_url(&self.url).is_some()
+    }
+    fn get_direct_url(&self) -> Option<String> {
+        direct_url(&self.url)
+    }
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+    fn get_type_name(&self) -> &'static str {
+        "OneDrive"
+    }
+    fn priority() -> u8 {
+        SITE_PRIORITY
+    }
+    fn hosts() -> &'static [&'static str] {
+        &["1drv.ms", "onedrive.live.com"]
+    }
+    fn from_url(url: &Url) -> Option<Self> {
+        Some(OneDriveLink {
+            url: url.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::direct_url;
+
+    #[test]
+    fn redir_and_embed_links() {
+        assert_eq!(
+            direct_url("https://onedrive.live.com/redir?resid=ABC%21123&authkey=%21XYZ"),
+            Some("https://onedrive.live.com/download?resid=ABC%21123&authkey=%21XYZ".into())
+        );
+        assert_eq!(
+            direct_url("https://onedrive.live.com/embed?cid=ABC&resid=ABC%21123"),
+            Some("https://onedrive.live.com/download?cid=ABC&resid=ABC%21123".into())
+        );
+        assert_eq!(
+            direct_url("https://onedrive.live.com/download?resid=ABC%21123"),
+            Some("https://onedrive.live.com/download?resid=ABC%21123".into())
+        );
+    }
+
+    #[test]
+    fn viewer_links_are_not_downloadable() {
+        assert_eq!(direct_url("https://1drv.ms/u/s!AbCdEf"), None);
+        assert_eq!(
+            direct_url("https://onedrive.live.com/?cid=ABC&id=ABC%21123"),
+            None
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+
+use crate::{
+    charts::{self, MAX_CHART_SIZE},
+    extract,
+    filetype::{self, FileType},
+    filter_entries, load_event_data, sources,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct InspectArgs {
+    /// 事件文件路径 (例如: events/BOFTT.toml)
+    #[arg(short, long)]
+    event: PathBuf,
+
+    /// 下载目录
+    #[arg(short, long, default_value = "downloads")]
+    output: PathBuf,
+
+    /// 只检查特定作品编号 (例如: 1,2,3)
+    #[arg(long)]
+    entries: Option<String>,
+
+    /// 谱面记录文件，默认为事件文件旁的 <key>/charts.jsonl
+    #[arg(long)]
+    charts: Option<PathBuf>,
+}
+
+/// 下载目录中属于该作品的文件和目录 (以 "{no} - {title}" 开头)
+fn downloaded_paths(output_dir: &Path, entry: &sources::BmsEntry) -> Result<Vec<PathBuf>> {
+    let prefix = sources::generate_filename(entry);
+    if !output_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(output_dir)
+        .with_context(|| format!("无法读取目录: {:?}", output_dir))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+        })
+        // 跳过未完成的下载
+        .filter(|path| {
+            let name = path.to_string_lossy();
+            !(name.ends_with(".part") || name.ends_with(".part.toml"))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// ZIP中的文件名没有UTF-8标记时通常为Shift_JIS
+fn inspect_zip(no: &str, path: &Path) -> Result<Vec<charts::ChartRecord>> {
+    let file = fs::File::open(path).with_context(|| format!("无法打开文件: {:?}", path))?;
+    let mut archive =
+        zip::ZipArchive::new(file).with_context(|| format!("无法读取ZIP文件: {:?}", path))?;
+
+    // 与解压时相同，按所有文件名判断编码
+    let raw_names: Vec<Vec<u8>> = (0..archive.len())
+        .map(|i| Ok(archive.by_index_raw(i)?.name_raw().to_vec()))
+        .collect::<Result<_>>()?;
+    let encoding = extract::detect_encoding(raw_names.iter().map(Vec::as_slice));
+
+    let mut records = Vec::new();
+    for (i, raw_name) in raw_names.iter().enumerate() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = encoding.decode_without_bom_handling(raw_name).0;
+        if !charts::is_chart_file(&name) {
+            continue;
+        }
+        // 不相信ZIP中记录的大小，最多读取上限多一个字节
+        let mut bytes = Vec::new();
+        (&mut entry)
+            .take(MAX_CHART_SIZE + 1)
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("无法解压 {}: {:?}", name, path))?;
+        if bytes.len() as u64 > MAX_CHART_SIZE {
+            warn!("谱面文件过大，跳过: {} ({:?})", name, path);
+            continue;
+        }
+        records.push(charts::inspect_chart(no, &name, &bytes));
+    }
+    Ok(records)
+}
+
+fn inspect_dir(
+    no: &str,
+    root: &Path,
+    dir: &Path,
+    records: &mut Vec<charts::ChartRecord>,
+) -> Result<()> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("无法读取目录: {:?}", dir))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            inspect_dir(no, root, &path, records)?;
+            continue;
+        }
+        let name = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if !charts::is_chart_file(&name) {
+            continue;
+        }
+        if let Some(bytes) = charts::read_chart_file(&path)? {
+            records.push(charts::inspect_chart(no, &name, &bytes));
+        }
+    }
+    Ok(())
+}
+
+/// RAR、7z、LZH 先解压到临时目录再检查，检查后删除临时目录
+fn inspect_archive(no: &str, path: &Path, file_type: FileType) -> Result<Vec<charts::ChartRecord>> {
+    let temp =
+        std::env::temp_dir().join(format!("downloader-inspect-{}-{}", std::process::id(), no));
+    if temp.exists() {
+        fs::remove_dir_all(&temp).with_context(|| format!("删除目录失败: {:?}", temp))?;
+    }
+    let result = extract::extract_to(path, file_type, &temp).and_then(|_| {
+        let mut records = Vec::new();
+        inspect_dir(no, &temp, &temp, &mut records)?;
+        Ok(records)
+    });
+    let _ = fs::remove_dir_all(&temp);
+    result
+}
+
+/// 检查一个已下载的作品包，支持ZIP、RAR、7z、LZH文件和已解压的目录
+fn inspect_path(no: &str, path: &Path) -> Result<Option<Vec<charts::ChartRecord>>> {
+    if path.is_dir() {
+        let mut records = Vec::new();
+        inspect_dir(no, path, path, &mut records)?;
+        return Ok(Some(records));
+    }
+
+    match filetype::sniff_file(path)? {
+        Some(filetype::ZIP) => return inspect_zip(no, path).map(Some),
+        Some(file_type) if extract::is_supported(file_type) => {
+            return inspect_archive(no, path, file_type).map(Some);
+        }
+        _ => {}
+    }
+    if charts::is_chart_file(&path.to_string_lossy()) {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let records = charts::read_chart_file(path)?
+            .map(|bytes| charts::inspect_chart(no, &name, &bytes))
+            .into_iter()
+            .collect();
+        return Ok(Some(records));
+    }
+
+    warn!("不支持的文件格式，请先解压: {:?}", path);
+    Ok(None)
+}
+
+pub fn run(args: InspectArgs) -> Result<()> {
+    let data = load_event_data(&args.event)?;
+    let entries = filter_entries(&data, args.entries.as_deref())?;
+    let charts_path = args
+        .charts
+        .clone()
+        .unwrap_or_else(|| charts::charts_path(&args.event));
+
+    let mut inspected: HashSet<String> = HashSet::new();
+    let mut new_records = Vec::new();
+    for entry in entries {
+        let paths = downloaded_paths(&args.output, entry)?;
+        if paths.is_empty() {
+            debug!("作品 #{} 尚未下载", entry.no);
+            continue;
+        }
+
+        let mut entry_records = Vec::new();
+        let mut found = false;
+        for path in &paths {
+            match inspect_path(&entry.no, path) {
+                Ok(Some(records)) => {
+                    found = true;
+                    entry_records.extend(records);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("检查作品 #{} 失败: {:#}", entry.no, e),
+            }
+        }
+        if !found {
+            continue;
+        }
+
+        if entry_records.is_empty() {
+            warn!("作品 #{} - {} 中没有找到谱面文件", entry.no, entry.title);
+        } else {
+            info!(
+                "作品 #{} - {}: {} 个谱面",
+                entry.no,
+                entry.title,
+                entry_records.len()
+            );
+        }
+        // 同一个谱面可能同时存在于压缩包和解压后的目录中
+        let mut seen = HashSet::new();
+        entry_records.retain(|record| seen.insert(record.sha256.clone()));
+        inspected.insert(entry.no.clone());
+        new_records.extend(entry_records);
+    }
+
+    if inspected.is_empty() {
+        warn!("没有找到已下载的作品: {:?}", args.output);
+        return Ok(());
+    }
+
+    // 重新检查的作品替换原有记录，其他作品的记录保留
+    let mut records = charts::load_charts(&charts_path)?;
+    records.retain(|record| !inspected.contains(&record.no));
+    records.extend(new_records);
+    records.sort_by(|a, b| {
+        let key = |no: &str| no.parse::<u32>().unwrap_or(u32::MAX);
+        (key(&a.no), &a.no, &a.file).cmp(&(key(&b.no), &b.no, &b.file))
+    });
+    charts::save_charts(&charts_path, &records)?;
+
+    info!(
+        "检查了 {} 个作品，共 {} 个谱面记录 -> {:?}",
+        inspected.len(),
+        records.len(),
+        charts_path
+    );
+    Ok(())
+}
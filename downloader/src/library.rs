@@ -0,0 +1,354 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{charts, load_event_data, sources};
+
+#[derive(clap::Args, Debug)]
+pub struct LibraryArgs {
+    /// 事件文件路径 (例如: events/BOFTT.toml)
+    #[arg(short, long)]
+    event: PathBuf,
+
+    /// beatoraja 的 songdata.db
+    #[arg(long, required_unless_present = "library", conflicts_with = "library")]
+    songdata: Option<PathBuf>,
+
+    /// BMS曲库目录，扫描其中所有谱面
+    #[arg(long)]
+    library: Option<PathBuf>,
+
+    /// 下载目录，结果保存为其中的 <key>.library.toml
+    #[arg(short, long, default_value = "downloads")]
+    output: PathBuf,
+
+    /// 谱面记录文件，默认为事件文件旁的 <key>/charts.jsonl
+    #[arg(long)]
+    charts: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnedStatus {
+    /// 所有已知谱面都在曲库中，或标题和作者匹配
+    Owned,
+    /// 只有部分已知谱面在曲库中
+    Partial,
+    Missing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMethod {
+    Hash,
+    Title,
+}
+
+/// 一个作品的匹配结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryOwnership {
+    pub no: String,
+    pub status: OwnedStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_by: Option<MatchMethod>,
+    /// 曲库中已有的谱面数 / 已知的谱面数
+    #[serde(default)]
+    pub owned_charts: usize,
+    #[serde(default)]
+    pub known_charts: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LibraryReport {
+    pub entries: Vec<EntryOwnership>,
+}
+
+/// 作者栏互相包含时，较短的一方至少需要的字符数，避免一两个字的作者名误匹配
+const MIN_ARTIST_OVERLAP: usize = 3;
+
+/// 曲库中的谱面
+#[derive(Debug, Default)]
+struct Library {
+    md5: HashSet<String>,
+    sha256: HashSet<String>,
+    /// 规范化后的标题 -> 作者
+    songs: HashMap<String, Vec<String>>,
+    charts: usize,
+}
+
+impl Library {
+    fn add_song(&mut self, title: &str, artist: &str) {
+        self.songs
+            .entry(normalize(title))
+            .or_default()
+            .push(normalize(artist));
+        self.charts += 1;
+    }
+}
+
+/// 匹配结果文件: downloads/<key>.library.toml
+pub fn report_path(output_dir: &Path, event_path: &Path) -> PathBuf {
+    let key = event_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    output_dir.join(format!("{}.library.toml", key))
+}
+
+pub fn load_report(path: &Path) -> Result<LibraryReport> {
+    let content = fs::read_to_string(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+    toml::from_str(&content).with_context(|| format!("解析TOML文件失败: {:?}", path))
+}
+
+/// 标题和作者的规范化：NFKC、小写、去掉空白和标题末尾的难度标记 (例如 [ANOTHER])
+fn normalize(text: &str) -> String {
+    let mut text: String = text.nfkc().flat_map(char::to_lowercase).collect();
+    loop {
+        let trimmed = text.trim_end();
+        let stripped = [('[', ']'), ('(', ')'), ('【', '】'), ('<', '>'), ('-', '-')]
+            .iter()
+            .find_map(|(open, close)| {
+                let inner = trimmed.strip_suffix(*close)?;
+                let start = inner.rfind(*open)?;
+                (start > 0).then(|| trimmed[..start].to_string())
+            });
+        match stripped {
+            Some(stripped) => text = stripped,
+            None => break,
+        }
+    }
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn load_songdata(path: &Path) -> Result<Library> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("无法打开数据库: {:?}", path))?;
+    let mut statement = conn
+        .prepare("SELECT md5, sha256, title, subtitle, artist FROM song")
+        .with_context(|| format!("不是beatoraja的songdata.db: {:?}", path))?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        ))
+    })?;
+
+    let mut library = Library::default();
+    for row in rows {
+        let (md5, sha256, title, subtitle, artist) = row?;
+        if let Some(md5) = md5.filter(|v| !v.is_empty()) {
+            library.md5.insert(md5.to_lowercase());
+        }
+        if let Some(sha256) = sha256.filter(|v| !v.is_empty()) {
+            library.sha256.insert(sha256.to_lowercase());
+        }
+        let title = match (title, subtitle) {
+            (Some(title), Some(subtitle)) if !subtitle.is_empty() => {
+                format!("{} {}", title, subtitle)
+            }
+            (title, _) => title.unwrap_or_default(),
+        };
+        library.add_song(&title, &artist.unwrap_or_default());
+    }
+    Ok(library)
+}
+
+fn scan_dir(dir: &Path, library: &mut Library) -> Result<()> {
+    for path in fs::read_dir(dir)
+        .with_context(|| format!("无法读取目录: {:?}", dir))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+    {
+        if path.is_dir() {
+            scan_dir(&path, library)?;
+            continue;
+        }
+        let name = path.to_string_lossy();
+        if !charts::is_chart_file(&name) {
+            continue;
+        }
+        let bytes = match charts::read_chart_file(&path) {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("{:#}", e);
+                continue;
+            }
+        };
+        let chart = charts::inspect_chart("", &name, &bytes);
+        library.md5.insert(chart.md5);
+        library.sha256.insert(chart.sha256);
+        let title = match (&chart.title, &chart.subtitle) {
+            (Some(title), Some(subtitle)) => format!("{} {}", title, subtitle),
+            (title, _) => title.clone().unwrap_or_default(),
+        };
+        library.add_song(&title, chart.artist.as_deref().unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// 作者相同，或者一方包含另一方 (曲库中的作者经常带有谱面作者等附加信息)
+fn artist_matches(artist: &str, song_artist: &str) -> bool {
+    let (shorter, longer) = if artist.len() <= song_artist.len() {
+        (artist, song_artist)
+    } else {
+        (song_artist, artist)
+    };
+    shorter == longer || (shorter.chars().count() >= MIN_ARTIST_OVERLAP && longer.contains(shorter))
+}
+
+/// 标题相同且作者匹配，没有作者时不按标题匹配
+fn title_matches(library: &Library, entry: &sources::BmsEntry) -> bool {
+    let title = normalize(&entry.title);
+    let artist = normalize(&entry.name);
+    if title.is_empty() || artist.is_empty() {
+        return false;
+    }
+    library.songs.get(&title).is_some_and(|artists| {
+        artists
+            .iter()
+            .any(|song_artist| !song_artist.is_empty() && artist_matches(&artist, song_artist))
+    })
+}
+
+fn match_entry(
+    library: &Library,
+    entry: &sources::BmsEntry,
+    entry_charts: &[&charts::ChartRecord],
+) -> EntryOwnership {
+    let owned_charts = entry_charts
+        .iter()
+        .filter(|chart| library.md5.contains(&chart.md5) || library.sha256.contains(&chart.sha256))
+        .count();
+    let known_charts = entry_charts.len();
+
+    let (status, matched_by) = if known_charts > 0 && owned_charts == known_charts {
+        (OwnedStatus::Owned, Some(MatchMethod::Hash))
+    } else if owned_charts > 0 {
+        (OwnedStatus::Partial, Some(MatchMethod::Hash))
+    } else if title_matches(library, entry) {
+        (OwnedStatus::Owned, Some(MatchMethod::Title))
+    } else {
+        (OwnedStatus::Missing, None)
+    };
+
+    EntryOwnership {
+        no: entry.no.clone(),
+        status,
+        matched_by,
+        owned_charts,
+        known_charts,
+    }
+}
+
+pub fn run(args: LibraryArgs) -> Result<()> {
+    let data = load_event_data(&args.event)?;
+    let charts_path = args
+        .charts
+        .clone()
+        .unwrap_or_else(|| charts::charts_path(&args.event));
+    let known = charts::load_charts(&charts_path)?;
+    if known.is_empty() {
+        info!("没有谱面记录 {:?}，只按标题和作者匹配", charts_path);
+    }
+
+    let library = match (&args.songdata, &args.library) {
+        (Some(songdata), _) => load_songdata(songdata)?,
+        (None, Some(dir)) => {
+            let mut library = Library::default();
+            scan_dir(dir, &mut library)?;
+            library
+        }
+        (None, None) => return Err(anyhow::anyhow!("需要指定 --songdata 或 --library")),
+    };
+    info!("曲库中有 {} 个谱面", library.charts);
+
+    let mut report = LibraryReport::default();
+    for entry in &data.entries {
+        let entry_charts: Vec<&charts::ChartRecord> =
+            known.iter().filter(|chart| chart.no == entry.no).collect();
+        let ownership = match_entry(&library, entry, &entry_charts);
+        debug!("作品 #{}: {:?}", entry.no, ownership.status);
+        report.entries.push(ownership);
+    }
+
+    let count = |status: OwnedStatus| report.entries.iter().filter(|e| e.status == status).count();
+    info!(
+        "已拥有 {} 个，部分拥有 {} 个，缺少 {} 个",
+        count(OwnedStatus::Owned),
+        count(OwnedStatus::Partial),
+        count(OwnedStatus::Missing)
+    );
+
+    let path = report_path(&args.output, &args.event);
+    fs::create_dir_all(&args.output).with_context(|| format!("创建目录失败: {:?}", args.output))?;
+    fs::write(&path, toml::to_string_pretty(&report)?)
+        .with_context(|| format!("写入文件失败: {:?}", path))?;
+    info!("匹配结果已保存到 {:?}", path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, title: &str) -> sources::BmsEntry {
+        sources::BmsEntry {
+            no: "1".into(),
+            name: name.into(),
+            team: None,
+            title: title.into(),
+            size: String::new(),
+            addr: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn difficulty_suffix_stripped() {
+        assert_eq!(normalize("Song [ANOTHER]"), "song");
+        assert_eq!(normalize("Song -ANOTHER-"), "song");
+        assert_eq!(normalize("Song (feat. X) 【HYPER】 "), "song");
+        assert_eq!(normalize("Ｓｏｎｇ　Ｔｉｔｌｅ <7keys>"), "songtitle");
+    }
+
+    #[test]
+    fn leading_bracket_kept() {
+        assert_eq!(normalize("[BOF] Song"), "[bof]song");
+        assert_eq!(normalize("【ANOTHER】"), "【another】");
+        assert_eq!(normalize("-ANOTHER-"), "-another-");
+    }
+
+    #[test]
+    fn artist_overlap() {
+        assert!(artist_matches("ab", "ab"));
+        assert!(!artist_matches("ab", "xaby"));
+        assert!(artist_matches("abc", "xabcy"));
+        assert!(artist_matches("xabcy", "abc"));
+        assert!(!artist_matches("abc", "abd"));
+        // 按字符计数，不按字节
+        assert!(!artist_matches("かな", "かなた"));
+        assert!(artist_matches("かなた", "obj.かなた"));
+    }
+
+    #[test]
+    fn title_and_artist() {
+        let mut library = Library::default();
+        library.add_song("Song [ANOTHER]", "obj.Artist / BGA: someone");
+        library.add_song("Untitled", "");
+        assert!(title_matches(&library, &entry("Artist", "Song")));
+        assert!(!title_matches(&library, &entry("Ar", "Song")));
+        assert!(!title_matches(&library, &entry("Other", "Song")));
+        assert!(!title_matches(&library, &entry("Artist", "Untitled")));
+        assert!(!title_matches(&library, &entry("", "Song")));
+    }
+}
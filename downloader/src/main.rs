@@ -1,14 +1,29 @@
 use std::{
     fs,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use indicatif::HumanBytes;
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 
+mod charts;
+mod disposition;
+mod extract;
+mod filetype;
+mod hosts;
+mod inspect;
+mod library;
+mod progress;
+mod queue;
+mod retry;
 mod sources;
+mod state;
+mod stats;
+mod verify;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct BmsData {
@@ -16,11 +31,21 @@ struct BmsData {
 }
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// 事件文件路径 (例如: events/BOFTT.toml)
-    #[arg(short, long)]
-    event: PathBuf,
+    #[arg(short, long, required = true)]
+    event: Option<PathBuf>,
 
     /// 输出目录
     #[arg(short, long, default_value = "downloads")]
@@ -34,11 +59,53 @@ struct Args {
     #[arg(long)]
     interactive: bool,
 
+    /// 同时下载的作品数
+    #[arg(short, long, default_value_t = 4)]
+    jobs: usize,
+
+    /// 每个站点同时下载数的上限，默认按站点设置 (Google Drive 为1，其他为4)
+    #[arg(long)]
+    per_host: Option<usize>,
+
+    /// 下载失败后最多重试的次数，404等不会成功的错误不重试
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// 第一次重试前等待的秒数，之后每次加倍
+    #[arg(long, default_value_t = 2)]
+    retry_delay: u64,
+
+    /// 实际大小与声明大小之差允许的百分比，超过时文件被移到 quarantine/
+    #[arg(long, default_value_t = 10.0)]
+    size_tolerance: f64,
+
+    /// 忽略下载记录，重新下载已完成的作品和未通过验证的链接
+    #[arg(long)]
+    force: bool,
+
+    /// 下载完成后解压到以作品命名的目录 (支持ZIP、RAR、7z、LZH)
+    #[arg(long)]
+    extract: bool,
+
+    /// 只下载曲库中缺少的作品，需要先运行 library 子命令
+    #[arg(long)]
+    missing_only: bool,
+
     /// 日志级别 (trace, debug, info, warn, error)
-    #[arg(long, default_value = "info")]
+    #[arg(long, default_value = "info", global = true)]
     log_level: String,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 统计事件文件的作品数、大小、团队比例和上传站点分布
+    Stats(stats::StatsArgs),
+    /// 检查已下载的作品包，记录其中各谱面的哈希和信息
+    Inspect(inspect::InspectArgs),
+    /// 与本地曲库 (beatoraja 的 songdata.db 或BMS目录) 比较，标记已拥有和缺少的作品
+    Library(library::LibraryArgs),
+}
+
 fn load_event_data(path: &Path) -> Result<BmsData> {
     info!("加载事件文件: {:?}", path);
     let content = fs::read_to_string(path).with_context(|| format!("无法读取文件: {:?}", path))?;
@@ -75,31 +142,143 @@ fn filter_entries<'a>(
     Ok(entries)
 }
 
+/// 解压之前下载完成但还没有解压的作品
+async fn extract_completed(
+    completed: &[&sources::BmsEntry],
+    output_dir: &Path,
+    state: &mut state::DownloadState,
+) -> Result<()> {
+    for entry in completed {
+        let Some(entry_state) = state.get_mut(&entry.no) else {
+            continue;
+        };
+        let Some(file) = entry_state
+            .file
+            .as_ref()
+            .filter(|_| entry_state.extracted.is_none())
+        else {
+            continue;
+        };
+        let archive = output_dir.join(file);
+        let extract = move || sources::extract_archive(&archive, None);
+        match tokio::task::spawn_blocking(extract).await? {
+            Ok(Some(dir)) => {
+                entry_state.extracted = dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned());
+                state.save()?;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("作品 #{} 解压失败: {:#}", entry.no, e),
+        }
+    }
+    Ok(())
+}
+
 async fn async_main(args: Args) -> Result<()> {
+    let Some(event) = &args.event else {
+        return Err(anyhow::anyhow!("需要指定事件文件 (--event)"));
+    };
+
     // 加载事件数据
-    let data = load_event_data(&args.event)?;
+    let data = load_event_data(event)?;
 
     // 过滤作品
-    let entries = filter_entries(&data, args.entries.as_deref())?;
+    let mut entries = filter_entries(&data, args.entries.as_deref())?;
+
+    if args.missing_only {
+        let report_path = library::report_path(&args.output, event);
+        if !report_path.exists() {
+            return Err(anyhow::anyhow!(
+                "没有找到曲库匹配结果 {:?}，请先运行 library 子命令",
+                report_path
+            ));
+        }
+        let report = library::load_report(&report_path)?;
+        let owned: Vec<&str> = report
+            .entries
+            .iter()
+            .filter(|e| e.status == library::OwnedStatus::Owned)
+            .map(|e| e.no.as_str())
+            .collect();
+        let before = entries.len();
+        entries.retain(|entry| !owned.contains(&entry.no.as_str()));
+        info!("跳过曲库中已有的 {} 个作品", before - entries.len());
+    }
 
     if entries.is_empty() {
         error!("没有找到要下载的作品");
         return Ok(());
     }
 
+    let mut state = state::DownloadState::load(&state::state_path(&args.output, event))?;
+    if !args.force {
+        let (completed, remaining): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|entry| state.is_completed(entry, &args.output));
+        entries = remaining;
+        if !completed.is_empty() {
+            info!(
+                "跳过已下载完成的 {} 个作品 (使用 --force 重新下载)",
+                completed.len()
+            );
+            if args.extract {
+                extract_completed(&completed, &args.output, &mut state).await?;
+            }
+        }
+        if entries.is_empty() {
+            info!("所有作品都已下载完成");
+            return Ok(());
+        }
+    }
+
     // 创建输出目录
     fs::create_dir_all(&args.output)?;
 
-    info!("开始下载 {} 个作品到 {:?}", entries.len(), args.output);
-
-    // 下载每个作品
+    // 先为每个作品选择链接，交互模式需要在开始下载前完成
+    let mut jobs = Vec::new();
     for entry in entries {
-        if let Err(e) = sources::download_entry(entry, &args.output, args.interactive).await {
-            error!("下载作品 #{} 失败: {}", entry.no, e);
+        let broken = if args.force {
+            &[]
+        } else {
+            state.broken_links(entry)
+        };
+        match sources::select_download_link_for_trait(entry, args.interactive, broken) {
+            Ok(Some(link)) => jobs.push(queue::Job {
+                entry: entry.clone(),
+                link,
+                extracted: state
+                    .get(&entry.no)
+                    .and_then(|entry_state| entry_state.extracted.clone()),
+            }),
+            Ok(None) => {}
+            Err(e) => error!("选择作品 #{} 的下载链接失败: {:#}", entry.no, e),
         }
     }
 
-    info!("下载完成！");
+    info!(
+        "开始下载 {} 个作品到 {:?} (同时下载 {} 个)",
+        jobs.len(),
+        args.output,
+        args.jobs
+    );
+    let limits = queue::Limits {
+        jobs: args.jobs,
+        per_host: args.per_host,
+    };
+    let options = sources::DownloadOptions {
+        verify: verify::VerifyOptions {
+            size_tolerance: args.size_tolerance / 100.0,
+        },
+        extract: args.extract,
+    };
+    let policy = retry::RetryPolicy {
+        retries: args.retries,
+        base_delay: Duration::from_secs(args.retry_delay),
+    };
+    let downloaded = queue::run(jobs, &args.output, limits, policy, options, &mut state).await;
+
+    info!("下载完成！共下载 {}", HumanBytes(downloaded));
     Ok(())
 }
 
@@ -108,16 +287,19 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     // 初始化日志
-    env_logger::Builder::from_default_env()
-        .filter_level(match args.log_level.as_str() {
-            "trace" => log::LevelFilter::Trace,
-            "debug" => log::LevelFilter::Debug,
-            "info" => log::LevelFilter::Info,
-            "warn" => log::LevelFilter::Warn,
-            "error" => log::LevelFilter::Error,
-            _ => log::LevelFilter::Info,
-        })
-        .init();
-
-    async_main(args).await
+    progress::init_logger(match args.log_level.as_str() {
+        "trace" => log::LevelFilter::Trace,
+        "debug" => log::LevelFilter::Debug,
+        "info" => log::LevelFilter::Info,
+        "warn" => log::LevelFilter::Warn,
+        "error" => log::LevelFilter::Error,
+        _ => log::LevelFilter::Info,
+    })?;
+
+    match args.command {
+        Some(Command::Stats(stats_args)) => stats::run(stats_args),
+        Some(Command::Inspect(inspect_args)) => inspect::run(inspect_args),
+        Some(Command::Library(library_args)) => library::run(library_args),
+        None => async_main(args).await,
+    }
 }
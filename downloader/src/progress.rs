@@ -0,0 +1,64 @@
+use std::sync::LazyLock;
+
+use anyhow::Result;
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
+use indicatif_log_bridge::LogWrapper;
+use log::LevelFilter;
+
+/// 所有进度条共用，输出日志时先隐藏进度条，避免日志和进度条混在一起
+static MULTI_PROGRESS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+pub fn init_logger(level: LevelFilter) -> Result<()> {
+    let logger = env_logger::Builder::from_default_env()
+        .filter_level(level)
+        .build();
+    LogWrapper::new(MULTI_PROGRESS.clone(), logger)
+        .try_init()
+        .map_err(|e| anyhow::anyhow!("初始化日志失败: {}", e))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// 在终端输出提示或等待输入时暂时隐藏进度条
+pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    MULTI_PROGRESS.suspend(f)
+}
+
+/// 总进度：已完成的作品数和已下载的字节数
+pub fn overall_bar(entries: usize) -> ProgressBar {
+    let bar = MULTI_PROGRESS.add(ProgressBar::new(entries as u64));
+    bar.set_style(
+        ProgressStyle::with_template("总进度 [{bar:30.green/white}] {pos}/{len} 个作品 {msg}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    bar.set_message(format!("已下载 {}", HumanBytes(0)));
+    bar
+}
+
+/// 单个文件的进度，没有大小时只显示已下载的字节数
+pub fn file_bar(name: &str, length: Option<u64>) -> ProgressBar {
+    let bar = match length {
+        Some(length) => {
+            let bar = ProgressBar::new(length);
+            bar.set_style(
+                ProgressStyle::with_template(
+                    "{msg:30!} [{bar:30.cyan/blue}] {bytes}/{total_bytes} {bytes_per_sec} {eta}",
+                )
+                .unwrap()
+                .progress_chars("=> "),
+            );
+            bar
+        }
+        None => {
+            let bar = ProgressBar::no_length();
+            bar.set_style(
+                ProgressStyle::with_template("{msg:30!} {spinner} {bytes} {bytes_per_sec}")
+                    .unwrap(),
+            );
+            bar
+        }
+    };
+    bar.set_message(name.to_string());
+    MULTI_PROGRESS.add(bar)
+}
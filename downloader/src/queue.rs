@@ -0,0 +1,192 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use anyhow::Result;
+use indicatif::HumanBytes;
+use log::{error, info, warn};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::{
+    hosts::LinkType,
+    progress,
+    retry::{self, ErrorKind, MAX_BACKOFF, RetryPolicy},
+    sources::{self, BmsEntry, DownloadOptions, DownloadedFile},
+    state::{DownloadState, EntryState, Outcome},
+    verify::QUARANTINE_DIR,
+};
+
+/// 一个作品和为它选择的下载链接
+pub struct Job {
+    pub entry: BmsEntry,
+    pub link: LinkType,
+    /// 下载记录中上次解压的目录名，重新解压时可以替换
+    pub extracted: Option<String>,
+}
+
+/// 并发下载的限制
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// 同时下载的作品数
+    pub jobs: usize,
+    /// 每个站点同时下载数的上限，没有时使用链接类型的设置
+    pub per_host: Option<usize>,
+}
+
+/// 暂时性的错误按重试策略等待后重试，等待期间不占用总名额，但保留站点的名额
+async fn download_with_retry(
+    job: &Job,
+    output_dir: &Path,
+    global: &Semaphore,
+    policy: RetryPolicy,
+    options: DownloadOptions,
+) -> Result<DownloadedFile> {
+    let mut attempt = 0;
+    loop {
+        let permit = global.acquire().await;
+        let result = sources::download_entry(
+            &job.entry,
+            job.link.as_ref(),
+            output_dir,
+            options,
+            job.extracted.as_deref(),
+        )
+        .await;
+        drop(permit);
+
+        let error = match result {
+            Ok(file) => return Ok(file),
+            Err(error) => error,
+        };
+        let ErrorKind::Transient { retry_after } = retry::classify(&error) else {
+            return Err(error);
+        };
+        if attempt >= policy.retries {
+            if policy.retries == 0 {
+                return Err(error);
+            }
+            return Err(error.context(format!("重试 {} 次后仍然失败", policy.retries)));
+        }
+        if retry_after.is_some_and(|wait| wait > MAX_BACKOFF) {
+            return Err(error.context("服务器要求等待的时间过长，放弃重试"));
+        }
+
+        let delay = policy.delay(attempt, retry_after);
+        attempt += 1;
+        warn!(
+            "作品 #{} 下载失败，{:.1} 秒后第 {} 次重试: {:#}",
+            job.entry.no,
+            delay.as_secs_f64(),
+            attempt,
+            error
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// 下载结果写入下载记录
+fn entry_state(job: &Job, result: &Result<DownloadedFile>) -> EntryState {
+    let mut state = EntryState {
+        no: job.entry.no.clone(),
+        outcome: Outcome::Completed,
+        addr: job.entry.addr.clone(),
+        link: job.link.get_url().to_string(),
+        file: None,
+        bytes: None,
+        sha256: None,
+        error: None,
+        time: chrono::Local::now().to_rfc3339(),
+        broken_links: Vec::new(),
+        extracted: None,
+    };
+    match result {
+        Ok(file) => {
+            let name = file
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            state.bytes = Some(file.size);
+            state.sha256 = Some(file.sha256.clone());
+            if let Some(reason) = &file.quarantined {
+                state.outcome = Outcome::Quarantined;
+                state.file = name.map(|name| format!("{}/{}", QUARANTINE_DIR, name));
+                state.error = Some(reason.clone());
+                state.broken_links.push(state.link.clone());
+            } else {
+                state.file = name;
+            }
+            state.extracted = file
+                .extracted
+                .as_ref()
+                .and_then(|dir| dir.file_name())
+                .map(|name| name.to_string_lossy().into_owned());
+        }
+        Err(e) => {
+            state.outcome = Outcome::Failed;
+            state.error = Some(format!("{:#}", e));
+        }
+    }
+    state
+}
+
+/// 并发下载所有作品，同一站点的下载数不超过其上限，返回下载的总字节数
+///
+/// 每个作品完成或失败后立即更新下载记录。
+pub async fn run(
+    jobs: Vec<Job>,
+    output_dir: &Path,
+    limits: Limits,
+    policy: RetryPolicy,
+    options: DownloadOptions,
+    state: &mut DownloadState,
+) -> u64 {
+    let overall = progress::overall_bar(jobs.len());
+    let global = Arc::new(Semaphore::new(limits.jobs.max(1)));
+    let mut host_limits: HashMap<String, Arc<Semaphore>> = HashMap::new();
+
+    let mut tasks = JoinSet::new();
+    for job in jobs {
+        let host = sources::provider_name(job.link.as_ref());
+        let cap = limits
+            .per_host
+            .map_or(job.link.max_concurrent(), |per_host| {
+                per_host.min(job.link.max_concurrent())
+            })
+            .max(1);
+        let host_limit = host_limits
+            .entry(host)
+            .or_insert_with_key(|host| {
+                info!("站点 {} 最多同时下载 {} 个作品", host, cap);
+                Arc::new(Semaphore::new(cap))
+            })
+            .clone();
+        let global = global.clone();
+        let output_dir = output_dir.to_path_buf();
+
+        tasks.spawn(async move {
+            // 先取得站点的名额，等待站点的任务不占用总名额
+            let _host_permit = host_limit.acquire_owned().await;
+            let result = download_with_retry(&job, &output_dir, &global, policy, options).await;
+            (job, result)
+        });
+    }
+
+    let mut downloaded = 0u64;
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((job, result)) => {
+                match &result {
+                    Ok(file) => downloaded += file.downloaded,
+                    Err(e) => error!("下载作品 #{} 失败: {:#}", job.entry.no, e),
+                }
+                state.record(entry_state(&job, &result));
+                if let Err(e) = state.save() {
+                    error!("保存下载记录失败: {:#}", e);
+                }
+            }
+            Err(e) => error!("下载任务异常退出: {}", e),
+        }
+        overall.inc(1);
+        overall.set_message(format!("已下载 {}", HumanBytes(downloaded)));
+    }
+    overall.finish_and_clear();
+    downloaded
+}
@@ -0,0 +1,241 @@
+use std::{collections::hash_map::RandomState, fmt, hash::BuildHasher, io, time::Duration};
+
+use chrono::Utc;
+use reqwest::StatusCode;
+
+/// 重试前最长的等待时间，服务器要求等待更久时放弃重试
+///
+/// 等待期间保留站点的名额，不能让一个任务长时间占用。
+pub const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// 能够判断是否值得重试的下载错误
+#[derive(Debug)]
+pub enum DownloadError {
+    /// 超时、连接中断、5xx、429 等，稍后重试可能成功
+    Transient {
+        message: String,
+        /// 服务器通过 Retry-After 要求的等待时间
+        retry_after: Option<Duration>,
+    },
+    /// 404、403、配额用尽、文件已被删除等，重试也不会成功
+    Permanent(String),
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::Transient { message, .. } | DownloadError::Permanent(message) => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+impl DownloadError {
+    /// 按HTTP状态码分类：408、429和5xx可以重试，其他都不重试
+    pub fn from_status(url: &str, status: StatusCode, retry_after: Option<Duration>) -> Self {
+        let message = format!("HTTP请求失败: {} - {}", url, status);
+        if status == StatusCode::REQUEST_TIMEOUT
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || status.is_server_error()
+        {
+            DownloadError::Transient {
+                message,
+                retry_after,
+            }
+        } else {
+            DownloadError::Permanent(message)
+        }
+    }
+}
+
+/// 错误的分类结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Transient { retry_after: Option<Duration> },
+    Permanent,
+}
+
+/// 沿着错误链判断是否值得重试，无法判断的错误不重试
+pub fn classify(error: &anyhow::Error) -> ErrorKind {
+    for cause in error.chain() {
+        if let Some(error) = cause.downcast_ref::<DownloadError>() {
+            return match error {
+                DownloadError::Transient { retry_after, .. } => ErrorKind::Transient {
+                    retry_after: *retry_after,
+                },
+                DownloadError::Permanent(_) => ErrorKind::Permanent,
+            };
+        }
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            if let Some(status) = error.status() {
+                return match DownloadError::from_status("", status, None) {
+                    DownloadError::Transient { .. } => ErrorKind::Transient { retry_after: None },
+                    DownloadError::Permanent(_) => ErrorKind::Permanent,
+                };
+            }
+            if error.is_timeout()
+                || error.is_connect()
+                || error.is_request()
+                || error.is_body()
+                || error.is_decode()
+            {
+                return ErrorKind::Transient { retry_after: None };
+            }
+        }
+        if let Some(error) = cause.downcast_ref::<io::Error>() {
+            return match error.kind() {
+                io::ErrorKind::TimedOut
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::Interrupted => ErrorKind::Transient { retry_after: None },
+                _ => ErrorKind::Permanent,
+            };
+        }
+    }
+    ErrorKind::Permanent
+}
+
+/// 解析 Retry-After：秒数或HTTP日期
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let seconds = (date.with_timezone(&Utc) - Utc::now()).num_seconds().max(0);
+    Some(Duration::from_secs(seconds as u64))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 失败后最多重试的次数
+    pub retries: u32,
+    /// 第一次重试前的等待时间，之后每次加倍
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// 第 attempt 次重试 (从0开始) 前的等待时间，服务器要求的等待时间优先
+    ///
+    /// 都不超过 `MAX_BACKOFF`。
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(MAX_BACKOFF);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_BACKOFF);
+        // 0.5 ~ 1.5 倍的随机抖动，避免同时失败的任务同时重试
+        backoff.mul_f64(0.5 + random_fraction())
+    }
+}
+
+/// [0, 1) 之间的随机数，RandomState 每次使用不同的随机种子
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().hash_one(()) >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(status: StatusCode) -> ErrorKind {
+        let error = DownloadError::from_status("https://example.com/a.zip", status, None);
+        classify(&anyhow::Error::from(error).context("下载作品 #1 失败"))
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+        assert_eq!(parse_retry_after("-1"), None);
+    }
+
+    #[test]
+    fn retry_after_dates() {
+        let future = (Utc::now() + chrono::Duration::seconds(90)).to_rfc2822();
+        let wait = parse_retry_after(&future).unwrap();
+        assert!(wait > Duration::from_secs(80) && wait <= Duration::from_secs(90));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn status_classes() {
+        let transient = ErrorKind::Transient { retry_after: None };
+        assert_eq!(kind(StatusCode::TOO_MANY_REQUESTS), transient);
+        assert_eq!(kind(StatusCode::SERVICE_UNAVAILABLE), transient);
+        assert_eq!(kind(StatusCode::INTERNAL_SERVER_ERROR), transient);
+        assert_eq!(kind(StatusCode::REQUEST_TIMEOUT), transient);
+        assert_eq!(kind(StatusCode::NOT_FOUND), ErrorKind::Permanent);
+        assert_eq!(kind(StatusCode::FORBIDDEN), ErrorKind::Permanent);
+    }
+
+    #[test]
+    fn error_chain() {
+        let error = DownloadError::Transient {
+            message: "429".into(),
+            retry_after: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(
+            classify(&anyhow::Error::from(error).context("外层")),
+            ErrorKind::Transient {
+                retry_after: Some(Duration::from_secs(30))
+            }
+        );
+        let reset = io::Error::from(io::ErrorKind::ConnectionReset);
+        assert_eq!(
+            classify(&anyhow::Error::from(reset)),
+            ErrorKind::Transient { retry_after: None }
+        );
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(
+            classify(&anyhow::Error::from(missing)),
+            ErrorKind::Permanent
+        );
+        assert_eq!(classify(&anyhow::anyhow!("未知错误")), ErrorKind::Permanent);
+    }
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy {
+            retries: 10,
+            base_delay: Duration::from_secs(2),
+        };
+        for attempt in 0..3 {
+            let base = 2 * 2u64.pow(attempt);
+            let delay = policy.delay(attempt, None);
+            assert!(delay >= Duration::from_secs(base) / 2, "{:?}", delay);
+            assert!(delay <= Duration::from_secs(base) * 3 / 2, "{:?}", delay);
+        }
+        for attempt in [20, 31, 32, u32::MAX] {
+            assert!(policy.delay(attempt, None) <= MAX_BACKOFF.mul_f64(1.5));
+        }
+    }
+
+    #[test]
+    fn retry_after_capped() {
+        let policy = RetryPolicy {
+            retries: 3,
+            base_delay: Duration::from_secs(2),
+        };
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(30))),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(86400))),
+            MAX_BACKOFF
+        );
+    }
+}
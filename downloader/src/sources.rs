@@ -1,15 +1,22 @@
 use std::{
-    any::Any,
-    fs,
-    io::{BufReader, Read},
+    fs, io,
     path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Result};
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+    charts, extract, filetype,
+    hosts::{self, LinkType, LinkTypeTrait, host_matches, strong_etag},
+    progress,
+    verify::{self, QuarantineInfo, VerifyOptions},
+};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BmsEntry {
     pub no: String,
     pub name: String,
@@ -20,326 +27,123 @@ pub struct BmsEntry {
     pub addr: Vec<String>,
 }
 
-pub trait LinkTypeTrait: std::fmt::Debug + Any {
-    fn is_downloadable(&self) -> bool;
-    fn get_direct_url(&self) -> Option<String>;
-    #[allow(unused)]
-    fn get_url(&self) -> &str;
-    #[allow(unused)]
-    fn get_type_name(&self) -> &'static str;
-    fn from_url(url: &str) -> Option<Self>
-    where
-        Self: Sized;
-
-    fn as_any(&self) -> &dyn Any;
-}
-
-#[derive(Debug, Clone)]
-pub struct DirectLink {
-    pub url: String,
-}
-
-#[derive(Debug, Clone)]
-pub struct GoogleDriveLink {
-    pub share_id: String,
-}
-
-#[derive(Debug, Clone)]
-pub struct DropboxLink {
-    pub share_id: String,
-}
-
-#[derive(Debug, Clone)]
-pub struct OneDriveLink {
-    pub url: String,
+/// 解析作品的下载地址，只接受带有域名的 http/https URL
+pub fn parse_link_url(addr: &str) -> Option<Url> {
+    let addr = addr.trim();
+    // 常见的笔误: "http://https://..."
+    let addr = ["http://", "https://"]
+        .iter()
+        .find_map(|scheme| {
+            let rest = addr.strip_prefix(scheme)?;
+            (rest.starts_with("http://") || rest.starts_with("https://")).then_some(rest)
+        })
+        .unwrap_or(addr);
+    let url = Url::parse(addr).ok()?;
+    let valid = matches!(url.scheme(), "http" | "https")
+        && url.host_str().is_some_and(|host| host.contains('.'));
+    valid.then_some(url)
 }
 
-#[derive(Debug, Clone)]
-pub struct MediaFireLink {
-    pub url: String,
+/// 从URL中提取主机名 (不含端口)
+pub fn url_host(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    url.host_str()
+        .filter(|host| !host.is_empty())
+        .map(str::to_lowercase)
 }
 
-// 为所有具体类型实现Debug trait以便格式化输出
-pub type LinkType = Box<dyn LinkTypeTrait>;
-
-// 为每个具体类型实现 LinkTypeTrait
-impl LinkTypeTrait for DirectLink {
-    fn is_downloadable(&self) -> bool {
-        true
-    }
-    fn get_direct_url(&self) -> Option<String> {
-        Some(self.url.clone())
-    }
-    fn get_url(&self) -> &str {
-        &self.url
-    }
-    fn get_type_name(&self) -> &'static str {
-        "Direct"
-    }
-    fn from_url(url: &str) -> Option<Self> {
-        if url.starts_with("http://") || url.starts_with("https://") {
-            Some(DirectLink {
-                url: url.to_string(),
-            })
-        } else {
-            None
-        }
-    }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-}
-
-impl LinkTypeTrait for GoogleDriveLink {
-    fn is_downloadable(&self) -> bool {
-        true
-    }
-    fn get_direct_url(&self) -> Option<String> {
-        if self.share_id.starts_with("https://") {
-            Some(self.share_id.clone())
-        } else {
-            Some(format!(
-                "https://drive.google.com/uc?export=download&id={}",
-                self.share_id
-            ))
-        }
-    }
-    fn get_url(&self) -> &str {
-        &self.share_id
-    }
-    fn get_type_name(&self) -> &'static str {
-        "GoogleDrive"
-    }
-    fn from_url(url: &str) -> Option<Self> {
-        if url.starts_with("https://drive.google.com/")
-            || url.starts_with("https://drive.usercontent.google.com/")
-        {
-            if let Some(id) = extract_google_drive_id(url) {
-                Some(GoogleDriveLink { share_id: id })
-            } else {
-                Some(GoogleDriveLink {
-                    share_id: url.to_string(),
-                })
-            }
-        } else {
-            None
-        }
-    }
-
-    fn as_any(&self) -> &dyn Any {
-        self
+/// 常见上传站点的主机名与显示名，链接类型无法区分的直链按主机名归类
+const KNOWN_PROVIDERS: &[(&str, &str)] = &[
+    ("drive.google.com", "GoogleDrive"),
+    ("drive.usercontent.google.com", "GoogleDrive"),
+    ("docs.google.com", "GoogleDrive"),
+    ("dropbox.com", "Dropbox"),
+    ("dropboxusercontent.com", "Dropbox"),
+    ("getdropbox.com", "Dropbox"),
+    ("db.tt", "Dropbox"),
+    ("1drv.ms", "OneDrive"),
+    ("onedrive.live.com", "OneDrive"),
+    ("sdrv.ms", "OneDrive"),
+    ("skydrive.live.com", "OneDrive"),
+    ("office.live.com", "OneDrive"),
+    ("livefilestore.com", "OneDrive"),
+    ("mediafire.com", "MediaFire"),
+    ("mega.nz", "Mega"),
+    ("mega.co.nz", "Mega"),
+    ("axfc.net", "axfc"),
+    ("uploader.bms.ms", "uploader.bms.ms"),
+    ("getuploader.com", "getuploader"),
+    ("firestorage.jp", "firestorage"),
+    ("box.com", "Box"),
+    ("copy.com", "Copy"),
+];
+
+/// 已经停止服务的上传站点，可以是主机名或带路径前缀的地址
+pub const DEAD_HOSTS: &[&str] = &[
+    // Dropbox 的 Public 文件夹链接已于2017年停用
+    "dl.dropboxusercontent.com/u/",
+    "www.dropbox.com/u/",
+    // SkyDrive 短链接和旧域名
+    "sdrv.ms",
+    "skydrive.live.com",
+    "copy.com",
+    "megaupload.com",
+];
+
+/// 链接所属的上传站点：优先使用链接类型，直链按主机名归类
+pub fn provider_name(link: &dyn LinkTypeTrait) -> String {
+    let type_name = link.get_type_name();
+    if !link.is_fallback() {
+        return type_name.to_string();
     }
+    let url = link.get_url();
+    let Some(host) = url_host(url) else {
+        return type_name.to_string();
+    };
+    KNOWN_PROVIDERS
+        .iter()
+        .find(|(domain, _)| host_matches(&host, domain))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or(host)
 }
 
-impl LinkTypeTrait for DropboxLink {
-    fn is_downloadable(&self) -> bool {
-        true
-    }
-    fn get_direct_url(&self) -> Option<String> {
-        if self.share_id.contains("?dl=0") {
-            Some(self.share_id.replace("?dl=0", "?dl=1"))
-        } else if !self.share_id.contains("?dl=") {
-            Some(format!("{}?dl=1", self.share_id))
-        } else {
-            Some(self.share_id.clone())
-        }
-    }
-    fn get_url(&self) -> &str {
-        &self.share_id
-    }
-    fn get_type_name(&self) -> &'static str {
-        "Dropbox"
-    }
-    fn from_url(url: &str) -> Option<Self> {
-        if url.starts_with("https://www.dropbox.com/")
-            || url.starts_with("https://dl.dropboxusercontent.com/")
-        {
-            if let Some(id) = extract_dropbox_id(url) {
-                Some(DropboxLink { share_id: id })
+/// 链接是否指向已经停止服务的站点
+pub fn is_dead_host(url: &str, extra_dead_hosts: &[String]) -> bool {
+    let Some(host) = url_host(url) else {
+        return false;
+    };
+    let rest = url
+        .trim_start_matches("http://")
+        .trim_start_matches("https://");
+    DEAD_HOSTS
+        .iter()
+        .copied()
+        .chain(extra_dead_hosts.iter().map(String::as_str))
+        .any(|pattern| {
+            if pattern.contains('/') {
+                rest.starts_with(pattern)
             } else {
-                Some(DropboxLink {
-                    share_id: url.to_string(),
-                })
+                host_matches(&host, pattern)
             }
-        } else {
-            None
-        }
-    }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-}
-
-impl LinkTypeTrait for OneDriveLink {
-    fn is_downloadable(&self) -> bool {
-        true
-    }
-    fn get_direct_url(&self) -> Option<String> {
-        Some(self.url.clone())
-    }
-    fn get_url(&self) -> &str {
-        &self.url
-    }
-    fn get_type_name(&self) -> &'static str {
-        "OneDrive"
-    }
-    fn from_url(url: &str) -> Option<Self> {
-        if url.starts_with("https://1drv.ms/") {
-            Some(OneDriveLink {
-                url: url.to_string(),
-            })
-        } else {
-            None
-        }
-    }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-}
-
-impl LinkTypeTrait for MediaFireLink {
-    fn is_downloadable(&self) -> bool {
-        true
-    }
-    fn get_direct_url(&self) -> Option<String> {
-        Some(self.url.clone())
-    }
-    fn get_url(&self) -> &str {
-        &self.url
-    }
-    fn get_type_name(&self) -> &'static str {
-        "MediaFire"
-    }
-    fn from_url(url: &str) -> Option<Self> {
-        if url.starts_with("https://www.mediafire.com/") {
-            Some(MediaFireLink {
-                url: url.to_string(),
-            })
-        } else {
-            None
-        }
-    }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-}
-
-pub fn create_link_from_url(url: &str) -> LinkType {
-    // 尝试各种链接类型
-    if let Some(link) = DirectLink::from_url(url) {
-        Box::new(link)
-    } else if let Some(link) = GoogleDriveLink::from_url(url) {
-        Box::new(link)
-    } else if let Some(link) = DropboxLink::from_url(url) {
-        Box::new(link)
-    } else if let Some(link) = OneDriveLink::from_url(url) {
-        Box::new(link)
-    } else if let Some(link) = MediaFireLink::from_url(url) {
-        Box::new(link)
-    } else {
-        // 默认为DirectLink
-        Box::new(DirectLink {
-            url: url.to_string(),
         })
-    }
 }
 
-pub fn extract_google_drive_id(url: &str) -> Option<String> {
-    // 匹配格式: https://drive.google.com/file/d/ID/view
-    if let Some(start) = url.find("/file/d/") {
-        let id_start = start + 8;
-        if let Some(end) = url[id_start..].find("/") {
-            return Some(url[id_start..id_start + end].to_string());
-        }
-    }
-
-    // 匹配格式: https://drive.google.com/uc?id=ID
-    if let Some(start) = url.find("?id=") {
-        let id_start = start + 4;
-        if let Some(end) = url[id_start..].find("&") {
-            return Some(url[id_start..id_start + end].to_string());
-        } else {
-            return Some(url[id_start..].to_string());
-        }
-    }
-
-    // 匹配格式: https://drive.usercontent.google.com/download?id=ID
-    if let Some(start) = url.find("/download?id=") {
-        let id_start = start + 13;
-        if let Some(end) = url[id_start..].find("&") {
-            return Some(url[id_start..id_start + end].to_string());
-        } else {
-            return Some(url[id_start..].to_string());
-        }
-    }
-
-    // 匹配格式: https://drive.usercontent.google.com/u/0/uc?id=ID
-    if let Some(start) = url.find("/uc?id=") {
-        let id_start = start + 7;
-        if let Some(end) = url[id_start..].find("&") {
-            return Some(url[id_start..id_start + end].to_string());
-        } else {
-            return Some(url[id_start..].to_string());
-        }
-    }
-
-    None
-}
-
-pub fn extract_dropbox_id(url: &str) -> Option<String> {
-    // 匹配格式: https://www.dropbox.com/s/ID/filename
-    if let Some(start) = url.find("/s/") {
-        let id_start = start + 3;
-        if let Some(end) = url[id_start..].find("/") {
-            return Some(url[id_start..id_start + end].to_string());
-        }
-    }
-
-    // 匹配格式: https://www.dropbox.com/scl/fi/ID/filename
-    // 匹配格式: https://www.dropbox.com/scl/fo/ID/filename
-    // 匹配格式: https://dl.dropboxusercontent.com/scl/fi/ID/filename
-    for pattern in ["/scl/fi/", "/scl/fo/"] {
-        if let Some(start) = url.find(pattern) {
-            let id_start = start + pattern.len();
-            if let Some(end) = url[id_start..].find("/") {
-                return Some(url[id_start..id_start + end].to_string());
-            }
-        }
-    }
-
-    // 特殊处理 dropboxusercontent.com 格式
-    if url.starts_with("https://dl.dropboxusercontent.com/scl/fi/") {
-        let id_start = 40; // "https://dl.dropboxusercontent.com/scl/fi/".len()
-        if let Some(end) = url[id_start..].find("/") {
-            return Some(url[id_start..id_start + end].to_string());
-        }
-    }
-
-    None
-}
-
-pub fn is_valid_url(url: &str) -> bool {
-    // 基本URL格式验证
-    if !url.starts_with("http://") && !url.starts_with("https://") {
-        return false;
-    }
-
-    // 检查是否包含有效的域名部分
-    if let Some(colon_pos) = url.find("://") {
-        let after_protocol = &url[colon_pos + 3..];
-        if after_protocol.is_empty() || !after_protocol.contains('.') {
-            return false;
-        }
-    } else {
-        return false;
-    }
-
-    // 可以添加更多URL格式验证规则
-    true
+/// 解析作品声明的大小 (例如 "17000 KB", "1.5 MB")，返回字节数
+pub fn parse_declared_size(size: &str) -> Option<u64> {
+    let normalized = size.trim().replace(',', "").to_uppercase();
+    let split = normalized
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(normalized.len());
+    let (number, unit) = normalized.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let factor = match unit.trim() {
+        "B" | "BYTES" => 1.0,
+        "" | "KB" | "K" | "KIB" => 1024.0,
+        "MB" | "M" | "MIB" => 1024.0 * 1024.0,
+        "GB" | "G" | "GIB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * factor).round() as u64)
 }
 
 pub fn analyze_links(entry: &BmsEntry) -> (Vec<LinkType>, Vec<String>) {
@@ -348,24 +152,20 @@ pub fn analyze_links(entry: &BmsEntry) -> (Vec<LinkType>, Vec<String>) {
 
     for addr in &entry.addr {
         // 只接受以 http:// 或 https:// 开头的有效URL
-        if addr.starts_with("http://") || addr.starts_with("https://") {
-            // 进一步验证URL格式
-            if is_valid_url(addr) {
-                links.push(create_link_from_url(addr));
-            } else {
-                non_links.push(addr.clone());
-            }
-        } else {
-            non_links.push(addr.clone());
+        match parse_link_url(addr) {
+            Some(url) => links.push(hosts::create_link_from_url(&url)),
+            None => non_links.push(addr.clone()),
         }
     }
 
     (links, non_links)
 }
 
+/// 为作品选择下载链接，跳过 `broken` 中上次下载的文件未通过验证的链接
 pub fn select_download_link_for_trait(
     entry: &BmsEntry,
     interactive: bool,
+    broken: &[String],
 ) -> Result<Option<Box<dyn LinkTypeTrait>>> {
     let (links, non_links) = analyze_links(entry);
 
@@ -397,6 +197,24 @@ pub fn select_download_link_for_trait(
         return Ok(None);
     }
 
+    let (broken_links, downloadable_links): (Vec<_>, Vec<_>) = downloadable_links
+        .into_iter()
+        .partition(|link| broken.iter().any(|url| url == link.get_url()));
+    if downloadable_links.is_empty() {
+        warn!(
+            "作品 #{} - {} 的所有链接下载的文件都未通过验证，使用 --force 重新下载",
+            entry.no, entry.title
+        );
+        return Ok(None);
+    }
+    if !broken_links.is_empty() {
+        info!(
+            "作品 #{} 跳过 {} 个下载的文件未通过验证的链接",
+            entry.no,
+            broken_links.len()
+        );
+    }
+
     if downloadable_links.len() == 1 {
         // 只有一个可下载链接，直接使用
         let link = downloadable_links.into_iter().next().unwrap();
@@ -411,30 +229,28 @@ pub fn select_download_link_for_trait(
 
     // 多个链接的情况
     if interactive {
-        println!("\n作品 #{} - {}", entry.no, entry.title);
-        println!("作者: {}", entry.name);
-        if let Some(team) = &entry.team {
-            println!("团队: {}", team);
-        }
-        println!("大小: {}", entry.size);
-        println!("\n可用的下载链接:");
-
-        // 重新获取所有链接来显示调试信息
-        let all_links = analyze_links(entry).0;
-        let downloadable_links_for_display: Vec<_> = all_links
-            .iter()
-            .filter(|link| link.is_downloadable())
-            .collect();
-        for (i, link) in downloadable_links_for_display.iter().enumerate() {
-            let direct_url = link
-                .get_direct_url()
-                .unwrap_or_else(|| "无法获取直接链接".to_string());
-            println!("  {}. {:?} -> {}", i + 1, link, direct_url);
-        }
+        // 等待输入时隐藏进度条
+        let input = progress::suspend(|| -> Result<String> {
+            println!("\n作品 #{} - {}", entry.no, entry.title);
+            println!("作者: {}", entry.name);
+            if let Some(team) = &entry.team {
+                println!("团队: {}", team);
+            }
+            println!("大小: {}", entry.size);
+            println!("\n可用的下载链接:");
+
+            for (i, link) in downloadable_links.iter().enumerate() {
+                let direct_url = link
+                    .get_direct_url()
+                    .unwrap_or_else(|| "无法获取直接链接".to_string());
+                println!("  {}. {:?} -> {}", i + 1, link, direct_url);
+            }
 
-        println!("\n请选择要下载的链接 (输入数字，或按 Enter 跳过):");
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
+            println!("\n请选择要下载的链接 (输入数字，或按 Enter 跳过):");
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            Ok(input)
+        })?;
 
         if let Ok(choice) = input.trim().parse::<usize>()
             && choice > 0
@@ -452,519 +268,427 @@ pub fn select_download_link_for_trait(
             "作品 #{} - {} 有多个下载链接，请使用 --interactive 模式选择:",
             entry.no, entry.title
         );
-        // 重新获取所有链接来显示调试信息
-        let all_links = analyze_links(entry).0;
-        let downloadable_links_for_display: Vec<_> = all_links
-            .iter()
-            .filter(|link| link.is_downloadable())
-            .collect();
-        for (i, link) in downloadable_links_for_display.iter().enumerate() {
-            let direct_url = link
-                .get_direct_url()
-                .unwrap_or_else(|| "无法获取直接链接".to_string());
-            println!("  {}. {:?} -> {}", i + 1, link, direct_url);
-        }
+        progress::suspend(|| {
+            for (i, link) in downloadable_links.iter().enumerate() {
+                let direct_url = link
+                    .get_direct_url()
+                    .unwrap_or_else(|| "无法获取直接链接".to_string());
+                println!("  {}. {:?} -> {}", i + 1, link, direct_url);
+            }
+        });
         Ok(None)
     }
 }
 
-pub async fn download_google_drive_file(file_id: &str, output_path: &Path) -> Result<PathBuf> {
-    info!("下载Google Drive文件: {} -> {:?}", file_id, output_path);
-
-    // 确保输出目录存在
-    if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    // 创建HTTP客户端
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .redirect(reqwest::redirect::Policy::limited(10))
-        .build()
-        .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))?;
-
-    // 第一步：获取cookie和确认页面
-    let cookie_path = output_path.parent().unwrap().join("cookie");
-    let cookie_url = format!("https://drive.google.com/uc?export=download&id={}", file_id);
-
-    info!("第一步：获取cookie，URL: {}", cookie_url);
-
-    let cookie_response = client
-        .get(&cookie_url)
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("获取cookie失败: {} - {}", cookie_url, e))?;
-
-    // 保存cookie内容
-    let cookie_content = cookie_response
-        .text()
-        .await
-        .map_err(|e| anyhow::anyhow!("读取cookie响应失败: {} - {}", cookie_url, e))?;
-
-    info!("Cookie内容长度: {} 字节", cookie_content.len());
-
-    fs::write(&cookie_path, &cookie_content)
-        .with_context(|| format!("保存cookie失败: {:?}", cookie_path))?;
-
-    // 从HTML内容中提取下载URL和文件名
-    let download_url = if let Some(url) = extract_download_url_from_html(&cookie_content) {
-        info!("从HTML中提取到下载URL: {}", url);
-        url
-    } else {
-        // 如果没有找到完整的下载URL，尝试使用确认token
-        let confirm_token = extract_confirm_token_from_html(&cookie_content)?;
-        info!("提取到的确认token: '{}'", confirm_token);
-
-        if confirm_token.is_empty() {
-            info!("没有找到确认token，直接使用原始URL");
-            cookie_url.clone()
-        } else {
-            let url = format!(
-                "https://drive.google.com/uc?export=download&confirm={}&id={}",
-                confirm_token, file_id
-            );
-            info!("使用确认token构造下载URL: {}", url);
-            url
-        }
-    };
-
-    // 尝试从HTML中提取原始文件名
-    let original_filename = extract_filename_from_html(&cookie_content);
-
-    info!("第二步：下载文件，URL: {}", download_url);
-
-    let response = client
-        .get(&download_url)
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("下载文件失败: {} - {}", download_url, e))?;
+pub fn generate_filename(entry: &BmsEntry) -> String {
+    let mut filename = format!("{} - {}", entry.no, entry.title);
 
-    // 检查响应状态
-    let status = response.status();
-    info!("HTTP响应状态: {}", status);
+    // 清理文件名中的非法字符
+    filename = filename
+        .replace("/", "_")
+        .replace("\\", "_")
+        .replace(":", "_")
+        .replace("*", "_")
+        .replace("?", "_")
+        .replace("\"", "_")
+        .replace("<", "_")
+        .replace(">", "_")
+        .replace("|", "_");
 
-    // 尝试从Content-Disposition头中提取文件名
-    let mut filename = None;
-    if let Some(disposition) = response.headers().get("Content-Disposition") {
-        let disposition_str = disposition.to_str().unwrap_or("");
-        info!("Content-Disposition: {}", disposition_str);
-        filename = extract_filename_from_disposition(disposition_str);
+    // 如果文件名太长，在字符边界处截断
+    if filename.len() > 100 {
+        filename = format!("{}...", &filename[..filename.floor_char_boundary(97)]);
     }
 
-    // 确定最终的文件名：优先使用Content-Disposition，然后是HTML中的文件名，最后是默认名称
-    let final_output_path = if let Some(header_filename) = &filename {
-        let parent_dir = output_path.parent().unwrap();
-        let new_path = parent_dir.join(header_filename);
-        info!(
-            "使用Content-Disposition中的文件名: {} -> {:?}",
-            header_filename, new_path
-        );
-        new_path
-    } else if let Some(html_filename) = &original_filename {
-        let parent_dir = output_path.parent().unwrap();
-        let new_path = parent_dir.join(html_filename);
-        info!("使用HTML中的文件名: {} -> {:?}", html_filename, new_path);
-        new_path
-    } else {
-        output_path.to_path_buf()
-    };
-
-    let mut file = fs::File::create(&final_output_path)
-        .with_context(|| format!("创建文件失败: {:?}", final_output_path))?;
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| anyhow::anyhow!("读取响应失败: {} - {}", download_url, e))?;
-
-    info!("下载的数据长度: {} 字节", bytes.len());
-
-    std::io::Write::write_all(&mut file, &bytes)
-        .with_context(|| format!("写入文件失败: {:?}", final_output_path))?;
-
-    // 清理cookie文件
-    let _ = fs::remove_file(&cookie_path);
-
-    info!(
-        "Google Drive下载完成: {:?} ({} 字节)",
-        final_output_path,
-        bytes.len()
-    );
-    Ok(final_output_path)
+    filename
 }
 
-pub fn extract_filename_from_disposition(disposition: &str) -> Option<String> {
-    // 解析 Content-Disposition 头，格式如：
-    // attachment; filename="filename.zip"
-    // attachment; filename*=UTF-8''filename.zip
-    if let Some(start) = disposition.find("filename=") {
-        let filename_start = start + 9;
-        let filename_part = &disposition[filename_start..];
-
-        // 处理引号包围的文件名
-        if let Some(stripped) = filename_part.strip_prefix('"') {
-            if let Some(end) = stripped.find('"') {
-                return Some(stripped[..end].to_string());
-            }
-        }
-        // 处理没有引号的文件名
-        else if let Some(end) = filename_part.find(';') {
-            return Some(filename_part[..end].to_string());
-        } else {
-            return Some(filename_part.to_string());
-        }
-    }
-
-    // 处理 filename*=UTF-8'' 格式
-    if let Some(start) = disposition.find("filename*=UTF-8''") {
-        let filename_start = start + 16;
-        let filename_part = &disposition[filename_start..];
-        if let Some(end) = filename_part.find(';') {
-            return Some(filename_part[..end].to_string());
-        } else {
-            return Some(filename_part.to_string());
-        }
-    }
-
-    None
+/// 下载中的文件: "<文件名>.part"，完成后重命名为最终的文件名
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
 }
 
-pub fn extract_confirm_token_from_html(html_content: &str) -> Result<String> {
-    // 在HTML中查找确认token
-    // 通常格式为: <a href="/uc?export=download&confirm=TOKEN&id=FILE_ID"
-    if let Some(start) = html_content.find("confirm=") {
-        let token_start = start + 8;
-        if let Some(end) = html_content[token_start..].find('&') {
-            return Ok(html_content[token_start..token_start + end].to_string());
-        } else if let Some(end) = html_content[token_start..].find('"') {
-            return Ok(html_content[token_start..token_start + end].to_string());
-        } else {
-            return Ok(html_content[token_start..].to_string());
-        }
-    }
-
-    // 也尝试查找其他可能的格式
-    if let Some(start) = html_content.find("&confirm=") {
-        let token_start = start + 9;
-        if let Some(end) = html_content[token_start..].find('&') {
-            return Ok(html_content[token_start..token_start + end].to_string());
-        } else if let Some(end) = html_content[token_start..].find('"') {
-            return Ok(html_content[token_start..token_start + end].to_string());
-        } else {
-            return Ok(html_content[token_start..].to_string());
-        }
-    }
-
-    Ok(String::new())
+/// 未完成下载的响应信息: "<文件名>.part.toml"
+fn part_info_path(part_path: &Path) -> PathBuf {
+    let mut name = part_path.as_os_str().to_owned();
+    name.push(".toml");
+    PathBuf::from(name)
 }
 
-pub fn extract_download_url_from_html(html_content: &str) -> Option<String> {
-    // 查找隐藏字段的值
-    let mut id = None;
-    let mut export = None;
-    let mut confirm = None;
-    let mut uuid = None;
-
-    // 提取id
-    if let Some(start) = html_content.find("name=\"id\" value=\"") {
-        let value_start = start + 17;
-        if let Some(end) = html_content[value_start..].find('"') {
-            id = Some(html_content[value_start..value_start + end].to_string());
-        }
-    }
-
-    // 提取export
-    if let Some(start) = html_content.find("name=\"export\" value=\"") {
-        let value_start = start + 20;
-        if let Some(end) = html_content[value_start..].find('"') {
-            export = Some(html_content[value_start..value_start + end].to_string());
-        }
-    }
+/// 开始下载时记录的响应信息，续传前用来判断服务器上的文件是否变化
+#[derive(Debug, Serialize, Deserialize)]
+struct PartInfo {
+    /// 作品的原始链接，网盘的实际下载地址每次可能不同
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_length: Option<u64>,
+}
 
-    // 提取confirm
-    if let Some(start) = html_content.find("name=\"confirm\" value=\"") {
-        let value_start = start + 21;
-        if let Some(end) = html_content[value_start..].find('"') {
-            confirm = Some(html_content[value_start..value_start + end].to_string());
+impl PartInfo {
+    fn new(link: &dyn LinkTypeTrait, download: &hosts::Download) -> Self {
+        PartInfo {
+            url: link.get_url().to_string(),
+            etag: download.etag.clone(),
+            last_modified: download.last_modified.clone(),
+            content_length: download.content_length,
         }
     }
 
-    // 提取uuid
-    if let Some(start) = html_content.find("name=\"uuid\" value=\"") {
-        let value_start = start + 19;
-        if let Some(end) = html_content[value_start..].find('"') {
-            uuid = Some(html_content[value_start..value_start + end].to_string());
-        }
+    /// 续传时 If-Range 使用的值：强ETag，没有时为Last-Modified
+    fn validator(&self) -> Option<&str> {
+        strong_etag(&self.etag).or(self.last_modified.as_deref())
     }
 
-    info!(
-        "提取到的参数: id={:?}, export={:?}, confirm={:?}, uuid={:?}",
-        id, export, confirm, uuid
-    );
-
-    // 构造完整的下载URL
-    if let (Some(id_val), Some(export_val), Some(confirm_val), Some(uuid_val)) =
-        (id, export, confirm, uuid)
-    {
-        let url = format!(
-            "https://drive.usercontent.google.com/download?id={}&export={}&confirm={}&uuid={}",
-            id_val, export_val, confirm_val, uuid_val
-        );
-        info!("构造的下载URL: {}", url);
-        return Some(url);
+    /// 同一个链接且ETag或Last-Modified没有变化；两者都没有时无法判断，不续传
+    fn unchanged(&self, other: &PartInfo) -> bool {
+        let validator_matches = match (strong_etag(&self.etag), strong_etag(&other.etag)) {
+            (Some(a), Some(b)) => a == b,
+            _ => {
+                matches!((&self.last_modified, &other.last_modified), (Some(a), Some(b)) if a == b)
+            }
+        };
+        self.url == other.url && validator_matches && self.content_length == other.content_length
     }
-
-    None
 }
 
-pub fn extract_filename_from_html(html_content: &str) -> Option<String> {
-    // 从HTML中提取文件名，格式如: <a href="/open?id=...">filename.zip</a>
-    if let Some(start) = html_content.find(">")
-        && let Some(end) = html_content[start + 1..].find("<")
+/// 已下载 existing 字节的同一个链接可以续传时返回续传的位置和 If-Range
+fn resume_point(saved: &PartInfo, url: &str, existing: u64) -> Option<hosts::Resume> {
+    if saved.url != url || existing == 0 || saved.content_length.is_some_and(|len| existing >= len)
     {
-        let filename = &html_content[start + 1..start + 1 + end];
-        if filename.contains('.') && !filename.contains(' ') {
-            info!("从HTML中提取到文件名: {}", filename);
-            return Some(filename.to_string());
-        }
+        return None;
     }
-
-    None
+    Some(hosts::Resume {
+        offset: existing,
+        validator: saved.validator()?.to_string(),
+    })
 }
 
-pub fn is_valid_archive(file_path: &Path) -> Result<bool> {
-    let file =
-        fs::File::open(file_path).with_context(|| format!("无法打开文件: {:?}", file_path))?;
-
-    let mut reader = BufReader::new(file);
-    let mut header = [0u8; 4];
-
-    if reader.read_exact(&mut header).is_err() {
-        return Ok(false);
-    }
+/// 读取未完成下载的信息，可以续传时返回续传的位置和开始下载时的响应信息
+fn load_resume(part_path: &Path, info_path: &Path, url: &str) -> Option<(hosts::Resume, PartInfo)> {
+    let existing = fs::metadata(part_path).ok()?.len();
+    let content = fs::read_to_string(info_path).ok()?;
+    let saved: PartInfo = toml::from_str(&content).ok()?;
+    let resume = resume_point(&saved, url, existing)?;
+    Some((resume, saved))
+}
 
-    // 检查ZIP文件头 (PK)
-    if header[0] == 0x50 && header[1] == 0x4B {
-        return Ok(true);
-    }
+/// URL路径的最后一段
+fn url_file_name(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    url.path_segments()?
+        .next_back()
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+}
 
-    // 检查RAR文件头
-    if header[0] == 0x52 && header[1] == 0x61 && header[2] == 0x72 && header[3] == 0x21 {
-        return Ok(true);
-    }
+/// 下载完成后根据文件头选择扩展名，并把 .part 文件重命名为 "<output_path>.<扩展名>"
+fn finish_part(
+    part_path: &Path,
+    output_path: &Path,
+    link: &dyn LinkTypeTrait,
+    download: &hosts::Download,
+) -> Result<PathBuf> {
+    let file_type = filetype::sniff_file(part_path)?;
+    let url_names = [url_file_name(&download.url), url_file_name(link.get_url())];
+    let mut names: Vec<&str> = download
+        .filename
+        .iter()
+        .chain(url_names.iter().flatten())
+        .map(String::as_str)
+        .collect();
+    names.dedup();
+    let extension = filetype::choose_extension(file_type, &names);
+    match file_type {
+        Some(file_type) => info!("文件类型: {} ({:?})", file_type.name, part_path),
+        None => warn!(
+            "无法识别文件类型，保存为 .{}: {:?} (站点和链接中的文件名: {})",
+            extension,
+            part_path,
+            names.join(", ")
+        ),
+    }
+
+    let mut final_path = output_path.as_os_str().to_owned();
+    final_path.push(format!(".{}", extension));
+    let final_path = PathBuf::from(final_path);
+    fs::rename(part_path, &final_path)
+        .with_context(|| format!("重命名文件失败: {:?} -> {:?}", part_path, final_path))?;
+    Ok(final_path)
+}
 
-    // 检查7Z文件头
-    if header[0] == 0x37 && header[1] == 0x7A && header[2] == 0xBC && header[3] == 0xAF {
-        return Ok(true);
+/// 解析链接并把响应体流式写入文件，完成后按文件类型加上扩展名
+///
+/// 存在未完成的 .part 文件且服务器支持Range请求时从中断处继续下载。
+/// 返回最终的文件路径和本次下载的字节数。
+pub async fn download_link(
+    link: &dyn LinkTypeTrait,
+    output_path: &Path,
+    declared_size: Option<u64>,
+) -> Result<(PathBuf, u64)> {
+    let client = hosts::http_client()?;
+    let part_path = part_path(output_path);
+    let info_path = part_info_path(&part_path);
+    let resume = load_resume(&part_path, &info_path, link.get_url());
+    if let Some((resume, _)) = &resume {
+        info!("从 {} 字节处继续下载: {:?}", resume.offset, part_path);
     }
+    let mut download = link
+        .resolve(&client, resume.as_ref().map(|(resume, _)| resume))
+        .await?;
 
-    // 检查TAR文件头 (需要更多字节，但先检查前4个)
-    if header[0] == 0x75 && header[1] == 0x73 && header[2] == 0x74 && header[3] == 0x61 {
-        return Ok(true);
+    if let Some(filename) = &download.filename {
+        info!("站点提供的文件名: {}", filename);
     }
+    info!("下载: {} -> {:?}", download.url, output_path);
 
-    Ok(false)
-}
-
-pub async fn download_file(url: &str, output_path: &Path) -> Result<PathBuf> {
-    info!("下载: {} -> {:?}", url, output_path);
-
-    // 确保输出目录存在
     if let Some(parent) = output_path.parent() {
         fs::create_dir_all(parent)?;
     }
+    let mut part_info = PartInfo::new(link, &download);
+    if let Some((_, saved)) = &resume {
+        if download.offset == 0 {
+            info!("服务器上的文件已变化或不支持断点续传，重新下载完整文件");
+        } else if !saved.unchanged(&part_info) {
+            // 服务器没有按 If-Range 检查时，大小或ETag的变化说明不是同一个文件
+            info!("服务器上的文件已变化，重新下载: {:?}", part_path);
+            download = hosts::Download::get(&client, &download.url, None)
+                .await?
+                .or_filename(download.filename.take());
+            part_info = PartInfo::new(link, &download);
+        }
+    }
+
+    let mut file = if download.offset > 0 {
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&part_path)
+            .with_context(|| format!("无法打开文件: {:?}", part_path))?
+    } else {
+        fs::write(&info_path, toml::to_string_pretty(&part_info)?)
+            .with_context(|| format!("写入文件失败: {:?}", info_path))?;
+        fs::File::create(&part_path).with_context(|| format!("创建文件失败: {:?}", part_path))?
+    };
 
-    let mut response = surf::get(url)
-        .await
-        .map_err(|e| anyhow::anyhow!("HTTP请求失败: {} - {}", url, e))?;
-
-    let mut file = fs::File::create(output_path)
-        .with_context(|| format!("创建文件失败: {:?}", output_path))?;
-
-    let bytes = response
-        .body_bytes()
-        .await
-        .map_err(|e| anyhow::anyhow!("读取响应失败: {} - {}", url, e))?;
+    // 服务器没有返回大小时使用作品声明的大小
+    let name = output_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let bar = progress::file_bar(&name, download.content_length.or(declared_size));
+    bar.set_position(download.offset);
+    let result = download.write_to(&mut file, &bar).await;
+    bar.finish_and_clear();
+    let size = result.with_context(|| format!("写入文件失败: {:?}", part_path))?;
+
+    file.sync_all()
+        .with_context(|| format!("写入文件失败: {:?}", part_path))?;
+    drop(file);
+    let final_path = finish_part(&part_path, output_path, link, &download)?;
+    let _ = fs::remove_file(&info_path);
 
-    std::io::Write::write_all(&mut file, &bytes)
-        .with_context(|| format!("写入文件失败: {:?}", output_path))?;
+    info!(
+        "下载完成: {:?} ({} 字节)",
+        final_path,
+        download.offset + size
+    );
+    Ok((final_path, size))
+}
 
-    info!("下载完成: {:?}", output_path);
-    Ok(output_path.to_path_buf())
+/// 流式计算文件的SHA-256
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).with_context(|| format!("无法打开文件: {:?}", path))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).with_context(|| format!("无法读取文件: {:?}", path))?;
+    Ok(charts::hex(&hasher.finalize()))
 }
 
-pub fn generate_filename(entry: &BmsEntry) -> String {
-    let mut filename = format!("{} - {}", entry.no, entry.title);
+/// 下载完成的作品文件
+#[derive(Debug)]
+pub struct DownloadedFile {
+    pub path: PathBuf,
+    /// 文件的大小
+    pub size: u64,
+    /// 本次下载的字节数，断点续传时小于文件的大小
+    pub downloaded: u64,
+    pub sha256: String,
+    /// 未通过验证时为原因，文件已被移到隔离目录
+    pub quarantined: Option<String>,
+    /// 解压的目录
+    pub extracted: Option<PathBuf>,
+}
 
-    // 清理文件名中的非法字符
-    filename = filename
-        .replace("/", "_")
-        .replace("\\", "_")
-        .replace(":", "_")
-        .replace("*", "_")
-        .replace("?", "_")
-        .replace("\"", "_")
-        .replace("<", "_")
-        .replace(">", "_")
-        .replace("|", "_");
+/// 下载完成后对文件的处理
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadOptions {
+    pub verify: VerifyOptions,
+    /// 解压支持的压缩包
+    pub extract: bool,
+}
 
-    // 如果文件名太长，截断
-    if filename.len() > 100 {
-        filename = format!("{}...", &filename[..97]);
+/// 压缩包是支持的格式时解压到同名的目录，返回解压的目录
+///
+/// `previous` 为上次解压创建的目录，可以被替换。
+pub fn extract_archive(path: &Path, previous: Option<&Path>) -> Result<Option<PathBuf>> {
+    match filetype::sniff_file(path)? {
+        Some(file_type) if extract::is_supported(file_type) => {
+            info!("解压 {} 文件: {:?}", file_type.name, path);
+            extract::extract(path, file_type, previous).map(Some)
+        }
+        _ => {
+            info!("不是支持解压的压缩包，跳过解压: {:?}", path);
+            Ok(None)
+        }
     }
-
-    filename
 }
 
-pub async fn download_entry(entry: &BmsEntry, output_dir: &Path, interactive: bool) -> Result<()> {
+/// 用选择的链接下载一个作品，检查下载的文件并按需要解压
+///
+/// `previous_extracted` 为下载记录中上次解压的目录名。
+pub async fn download_entry(
+    entry: &BmsEntry,
+    link: &dyn LinkTypeTrait,
+    output_dir: &Path,
+    options: DownloadOptions,
+    previous_extracted: Option<&str>,
+) -> Result<DownloadedFile> {
     let filename = generate_filename(entry);
     let output_path = output_dir.join(&filename);
 
-    if let Some(link) = select_download_link_for_trait(entry, interactive)? {
-        let final_path = download_link_by_type(link.as_ref(), &output_path).await?;
+    let declared_size = parse_declared_size(&entry.size);
+    let (mut path, downloaded) = download_link(link, &output_path, declared_size).await?;
+
+    // 几百MB的文件读取需要一段时间，不要阻塞其他下载
+    let check_path = path.clone();
+    let (size, sha256, quarantined) = tokio::task::spawn_blocking(move || -> Result<_> {
+        let size = fs::metadata(&check_path)
+            .with_context(|| format!("无法读取文件: {:?}", check_path))?
+            .len();
+        let sha256 = file_sha256(&check_path)?;
+        let quarantined = verify::check(&check_path, declared_size, options.verify)?;
+        Ok((size, sha256, quarantined))
+    })
+    .await??;
+    if let Some(reason) = &quarantined {
+        let info = QuarantineInfo {
+            no: &entry.no,
+            link: link.get_url(),
+            reason,
+            bytes: size,
+            sha256: &sha256,
+            time: chrono::Local::now().to_rfc3339(),
+        };
+        path = verify::quarantine(&path, output_dir, &info)?;
+        warn!(
+            "作品 #{} 的文件未通过验证，已移到 {:?}: {}",
+            entry.no, path, reason
+        );
+    }
 
-        // 验证下载的文件是否为有效压缩包
-        if is_valid_archive(&final_path)? {
-            info!("文件验证成功: {:?} 是有效的压缩包", final_path);
-        } else {
-            warn!("文件验证失败: {:?} 不是有效的压缩包", final_path);
+    // 解压失败时保留压缩包，不影响下载的结果
+    let mut extracted = None;
+    if options.extract && quarantined.is_none() {
+        let archive = path.clone();
+        let previous = previous_extracted.map(|name| output_dir.join(name));
+        let extract = move || extract_archive(&archive, previous.as_deref());
+        match tokio::task::spawn_blocking(extract).await? {
+            Ok(dir) => extracted = dir,
+            Err(e) => warn!("作品 #{} 解压失败: {:#}", entry.no, e),
         }
     }
 
-    Ok(())
+    Ok(DownloadedFile {
+        path,
+        size,
+        downloaded,
+        sha256,
+        quarantined,
+        extracted,
+    })
 }
 
-pub fn extract_google_drive_id_from_url(url: &str) -> Option<String> {
-    // 匹配格式: https://drive.google.com/file/d/ID/view
-    if let Some(start) = url.find("/file/d/") {
-        let id_start = start + 8;
-        if let Some(end) = url[id_start..].find("/") {
-            return Some(url[id_start..id_start + end].to_string());
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::{PartInfo, resume_point};
 
-    // 匹配格式: https://drive.google.com/uc?id=ID 或 https://drive.google.com/uc?export=download&id=ID
-    if let Some(start) = url.find("?id=") {
-        let id_start = start + 4;
-        if let Some(end) = url[id_start..].find("&") {
-            return Some(url[id_start..id_start + end].to_string());
-        } else {
-            return Some(url[id_start..].to_string());
-        }
-    }
+    const URL: &str = "https://example.com/pack.zip";
+    const DATE: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
 
-    // 匹配格式: https://drive.google.com/uc?export=download&id=ID
-    if let Some(start) = url.find("&id=") {
-        let id_start = start + 4;
-        if let Some(end) = url[id_start..].find("&") {
-            return Some(url[id_start..id_start + end].to_string());
-        } else {
-            return Some(url[id_start..].to_string());
+    fn info(etag: Option<&str>, last_modified: Option<&str>, len: Option<u64>) -> PartInfo {
+        PartInfo {
+            url: URL.into(),
+            etag: etag.map(str::to_string),
+            last_modified: last_modified.map(str::to_string),
+            content_length: len,
         }
     }
 
-    None
-}
-
-pub async fn download_link_by_type(
-    link: &dyn LinkTypeTrait,
-    output_path: &Path,
-) -> Result<PathBuf> {
-    let type_name = link.get_type_name();
-
-    match type_name {
-        "Direct" => {
-            let direct_link = link.as_any().downcast_ref::<DirectLink>().unwrap();
-            download_file(&direct_link.url, output_path).await
-        }
-        "GoogleDrive" => {
-            let google_link = link.as_any().downcast_ref::<GoogleDriveLink>().unwrap();
-            let file_id = if google_link.share_id.starts_with("https://") {
-                if let Some(id) = extract_google_drive_id_from_url(&google_link.share_id) {
-                    id
-                } else {
-                    return Err(anyhow::anyhow!(
-                        "无法从Google Drive URL中提取文件ID: {}",
-                        google_link.share_id
-                    ));
-                }
-            } else {
-                google_link.share_id.clone()
-            };
-            download_google_drive_file(&file_id, output_path).await
-        }
-        "Dropbox" => {
-            let dropbox_link = link.as_any().downcast_ref::<DropboxLink>().unwrap();
-            let download_url = dropbox_link.get_direct_url().ok_or_else(|| {
-                anyhow::anyhow!("无法获取Dropbox下载链接: {}", dropbox_link.share_id)
-            })?;
-            download_file(&download_url, output_path).await
-        }
-        "OneDrive" => {
-            let onedrive_link = link.as_any().downcast_ref::<OneDriveLink>().unwrap();
-            let client = reqwest::Client::builder()
-                .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-                .redirect(reqwest::redirect::Policy::limited(5))
-                .build()
-                .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))?;
-
-            let response = client.head(&onedrive_link.url).send().await.map_err(|e| {
-                anyhow::anyhow!("访问OneDrive链接失败: {} - {}", onedrive_link.url, e)
-            })?;
-
-            let final_url = response.url().to_string();
-            download_file(&final_url, output_path).await
-        }
-        "MediaFire" => {
-            let mediafire_link = link.as_any().downcast_ref::<MediaFireLink>().unwrap();
-            let client = reqwest::Client::builder()
-                .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-                .build()
-                .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))?;
-
-            let response = client.get(&mediafire_link.url).send().await.map_err(|e| {
-                anyhow::anyhow!("访问MediaFire链接失败: {} - {}", mediafire_link.url, e)
-            })?;
-
-            let html_content = response.text().await.map_err(|e| {
-                anyhow::anyhow!("读取MediaFire页面失败: {} - {}", mediafire_link.url, e)
-            })?;
-
-            let download_url = extract_mediafire_download_url(&html_content).ok_or_else(|| {
-                anyhow::anyhow!("无法从MediaFire页面中提取下载链接: {}", mediafire_link.url)
-            })?;
-
-            download_file(&download_url, output_path).await
-        }
-        _ => Err(anyhow::anyhow!("不支持的链接类型: {}", type_name)),
+    fn validator(saved: &PartInfo, existing: u64) -> Option<String> {
+        resume_point(saved, URL, existing).map(|resume| resume.validator)
     }
-}
 
-pub fn extract_mediafire_download_url(html_content: &str) -> Option<String> {
-    // 在MediaFire页面中查找下载按钮的链接
-    // 通常格式为: <a href="下载链接" class="download_link" 或包含 download
-    if let Some(start) = html_content.find("download_link")
-        && let Some(href_start) = html_content[..start].rfind("href=\"")
-    {
-        let url_start = href_start + 6;
-        if let Some(end) = html_content[url_start..].find('"') {
-            let url = &html_content[url_start..url_start + end];
-            if url.starts_with("http") {
-                return Some(url.to_string());
-            }
-        }
+    #[test]
+    fn strong_etag_preferred() {
+        let saved = info(Some("\"abc\""), Some(DATE), Some(100));
+        assert_eq!(validator(&saved, 40), Some("\"abc\"".into()));
+        assert_eq!(resume_point(&saved, URL, 40).unwrap().offset, 40);
     }
 
-    // 尝试查找其他可能的下载链接模式
-    if let Some(start) = html_content.find("http") {
-        let potential_url = &html_content[start..];
-        if let Some(end) = potential_url.find('"') {
-            let url = &potential_url[..end];
-            if url.contains("download") && (url.contains("mediafire") || url.contains("dl")) {
-                return Some(url.to_string());
-            }
-        }
+    #[test]
+    fn weak_etag_falls_back_to_last_modified() {
+        assert_eq!(
+            validator(&info(Some("W/\"abc\""), Some(DATE), Some(100)), 40),
+            Some(DATE.into())
+        );
+        assert_eq!(
+            validator(&info(None, Some(DATE), None), 40),
+            Some(DATE.into())
+        );
+        assert_eq!(
+            validator(&info(Some("W/\"abc\""), None, Some(100)), 40),
+            None
+        );
+        assert_eq!(validator(&info(None, None, Some(100)), 40), None);
+    }
+
+    #[test]
+    fn existing_part_size() {
+        let saved = info(Some("\"abc\""), None, Some(100));
+        assert!(resume_point(&saved, URL, 0).is_none());
+        assert!(resume_point(&saved, URL, 100).is_none());
+        assert!(resume_point(&saved, URL, 150).is_none());
+        assert!(resume_point(&saved, URL, 99).is_some());
+        assert!(resume_point(&saved, "https://example.com/other.zip", 40).is_none());
+    }
+
+    #[test]
+    fn unchanged_validators() {
+        let saved = info(Some("\"abc\""), Some(DATE), Some(100));
+        assert!(saved.unchanged(&info(Some("\"abc\""), None, Some(100))));
+        assert!(!saved.unchanged(&info(Some("\"def\""), Some(DATE), Some(100))));
+        // 弱ETag不能比较，使用Last-Modified
+        let weak = info(Some("W/\"abc\""), Some(DATE), Some(100));
+        assert!(weak.unchanged(&info(Some("W/\"def\""), Some(DATE), Some(100))));
+        assert!(!weak.unchanged(&info(Some("W/\"abc\""), None, Some(100))));
+        let last_modified = info(None, Some(DATE), Some(100));
+        assert!(last_modified.unchanged(&info(None, Some(DATE), Some(100))));
+        assert!(!last_modified.unchanged(&info(
+            None,
+            Some("Thu, 22 Oct 2015 07:28:00 GMT"),
+            Some(100)
+        )));
+        assert!(!info(None, None, Some(100)).unchanged(&info(None, None, Some(100))));
+    }
+
+    #[test]
+    fn changed_content_length() {
+        let saved = info(Some("\"abc\""), None, Some(100));
+        assert!(!saved.unchanged(&info(Some("\"abc\""), None, Some(120))));
+        assert!(!saved.unchanged(&info(Some("\"abc\""), None, None)));
     }
-
-    None
 }
@@ -0,0 +1,173 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::sources::BmsEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Completed,
+    Failed,
+    /// 下载完成但未通过验证，文件已被移到隔离目录
+    Quarantined,
+}
+
+/// 一个作品最近一次下载的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryState {
+    pub no: String,
+    pub outcome: Outcome,
+    /// 下载时作品的地址，事件文件中的地址变化后重新下载
+    pub addr: Vec<String>,
+    /// 选择的下载链接
+    pub link: String,
+    /// 下载目录中的文件名，隔离的文件为 quarantine/ 中的路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 完成或失败的时间 (RFC 3339)
+    pub time: String,
+    /// 下载的文件未通过验证的链接，地址不变时不再使用
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub broken_links: Vec<String>,
+    /// 解压的目录名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extracted: Option<String>,
+}
+
+/// 下载目录中的下载记录，重新运行时跳过已完成的作品
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DownloadState {
+    #[serde(default)]
+    pub entries: Vec<EntryState>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// 下载记录文件: downloads/<key>.state.toml
+pub fn state_path(output_dir: &Path, event_path: &Path) -> PathBuf {
+    let key = event_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    output_dir.join(format!("{}.state.toml", key))
+}
+
+impl DownloadState {
+    /// 读取下载记录，文件不存在时为空
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut state: DownloadState = if path.exists() {
+            let content =
+                fs::read_to_string(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+            toml::from_str(&content).with_context(|| format!("解析TOML文件失败: {:?}", path))?
+        } else {
+            DownloadState::default()
+        };
+        state.path = path.to_path_buf();
+        Ok(state)
+    }
+
+    /// 先写入临时文件再重命名，中途退出时不会留下不完整的记录
+    pub fn save(&mut self) -> Result<()> {
+        self.entries.sort_by(|a, b| {
+            let key = |no: &str| no.parse::<u32>().unwrap_or(u32::MAX);
+            (key(&a.no), &a.no).cmp(&(key(&b.no), &b.no))
+        });
+        let content = toml::to_string_pretty(self)?;
+        let mut tmp_path = self.path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        fs::write(&tmp_path, content).with_context(|| format!("写入文件失败: {:?}", tmp_path))?;
+        fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("重命名文件失败: {:?} -> {:?}", tmp_path, self.path))
+    }
+
+    pub fn get(&self, no: &str) -> Option<&EntryState> {
+        self.entries.iter().find(|state| state.no == no)
+    }
+
+    pub fn get_mut(&mut self, no: &str) -> Option<&mut EntryState> {
+        self.entries.iter_mut().find(|state| state.no == no)
+    }
+
+    /// 替换作品原有的记录，地址没有变化时保留已损坏的链接
+    pub fn record(&mut self, mut state: EntryState) {
+        if let Some(previous) = self.get(&state.no)
+            && previous.addr == state.addr
+        {
+            for link in &previous.broken_links {
+                if !state.broken_links.contains(link) {
+                    state.broken_links.push(link.clone());
+                }
+            }
+        }
+        self.entries.retain(|existing| existing.no != state.no);
+        self.entries.push(state);
+    }
+
+    /// 作品的链接中下载的文件未通过验证的链接
+    pub fn broken_links(&self, entry: &BmsEntry) -> &[String] {
+        match self.get(&entry.no) {
+            Some(state) if state.addr == entry.addr => &state.broken_links,
+            _ => &[],
+        }
+    }
+
+    /// 作品已经下载完成，且事件文件中的地址和下载的文件都没有变化
+    pub fn is_completed(&self, entry: &BmsEntry, output_dir: &Path) -> bool {
+        let Some(state) = self.get(&entry.no) else {
+            return false;
+        };
+        match state.outcome {
+            Outcome::Failed => {
+                info!(
+                    "作品 #{} 上次下载失败，重新尝试: {}",
+                    entry.no,
+                    state.error.as_deref().unwrap_or_default()
+                );
+                false
+            }
+            Outcome::Quarantined => {
+                info!(
+                    "作品 #{} 上次下载的文件未通过验证: {}",
+                    entry.no,
+                    state.error.as_deref().unwrap_or_default()
+                );
+                false
+            }
+            Outcome::Completed if state.addr != entry.addr => {
+                info!("作品 #{} 的下载地址已变化，重新下载", entry.no);
+                false
+            }
+            Outcome::Completed => {
+                let size = state
+                    .file
+                    .as_ref()
+                    .and_then(|file| fs::metadata(output_dir.join(file)).ok())
+                    .map(|metadata| metadata.len());
+                match size {
+                    None => {
+                        info!("作品 #{} 已下载的文件不存在，重新下载", entry.no);
+                        false
+                    }
+                    Some(size) if state.bytes.is_some_and(|bytes| bytes != size) => {
+                        info!("作品 #{} 已下载的文件大小已变化，重新下载", entry.no);
+                        false
+                    }
+                    Some(_) => true,
+                }
+            }
+        }
+    }
+}
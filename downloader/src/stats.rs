@@ -0,0 +1,217 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::{load_event_data, sources};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatsFormat {
+    Table,
+    Json,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct StatsArgs {
+    /// 事件文件路径，默认为 events 目录下的所有文件
+    files: Vec<PathBuf>,
+
+    /// 输出格式
+    #[arg(short, long, value_enum, default_value = "table")]
+    format: StatsFormat,
+
+    /// 额外视为已停止服务的站点，可以是主机名或带路径前缀的地址 (例如: --dead-host axfc.net)
+    #[arg(long = "dead-host")]
+    dead_hosts: Vec<String>,
+}
+
+/// 一个事件文件的统计结果
+#[derive(Debug, Serialize)]
+struct EventStats {
+    event: String,
+    entries: usize,
+    /// 声明大小的总和 (字节)，无法解析的大小不计入
+    total_size: u64,
+    /// 声明大小的中位数 (字节)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    median_size: Option<u64>,
+    /// 无法解析大小的作品数
+    unknown_size: usize,
+    team_entries: usize,
+    solo_entries: usize,
+    /// 各上传站点的链接数
+    providers: BTreeMap<String, usize>,
+    /// 没有任何链接的作品编号
+    no_link: Vec<String>,
+    /// 含有已停止服务站点链接的作品编号
+    dead_link: Vec<String>,
+}
+
+fn default_event_files() -> Result<Vec<PathBuf>> {
+    let dir = Path::new("events");
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("无法读取目录: {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn median(values: &mut [u64]) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2)
+    } else {
+        Some(values[mid])
+    }
+}
+
+fn event_stats(path: &Path, dead_hosts: &[String]) -> Result<EventStats> {
+    let data = load_event_data(path)?;
+    let event = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    let mut sizes = Vec::new();
+    let mut providers = BTreeMap::new();
+    let mut no_link = Vec::new();
+    let mut dead_link = Vec::new();
+    let mut team_entries = 0;
+
+    for entry in &data.entries {
+        if entry.team.is_some() {
+            team_entries += 1;
+        }
+        if let Some(size) = sources::parse_declared_size(&entry.size) {
+            sizes.push(size);
+        }
+
+        let (links, _) = sources::analyze_links(entry);
+        if links.is_empty() {
+            no_link.push(entry.no.clone());
+        }
+        for link in &links {
+            *providers
+                .entry(sources::provider_name(link.as_ref()))
+                .or_default() += 1;
+        }
+        if links
+            .iter()
+            .any(|link| sources::is_dead_host(link.get_url(), dead_hosts))
+        {
+            dead_link.push(entry.no.clone());
+        }
+    }
+
+    Ok(EventStats {
+        event,
+        entries: data.entries.len(),
+        total_size: sizes.iter().sum(),
+        unknown_size: data.entries.len() - sizes.len(),
+        median_size: median(&mut sizes),
+        team_entries,
+        solo_entries: data.entries.len() - team_entries,
+        providers,
+        no_link,
+        dead_link,
+    })
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// 表格中每个事件显示的上传站点数，其余合并为"其他"
+const TABLE_PROVIDERS: usize = 10;
+
+fn print_table(stats: &[EventStats]) {
+    println!(
+        "{:<10} {:>6} {:>11} {:>10} {:>6} {:>6} {:>7} {:>7}",
+        "事件", "作品", "总大小", "中位数", "团队", "个人", "无链接", "失效站"
+    );
+    for event in stats {
+        println!(
+            "{:<12} {:>8} {:>14} {:>13} {:>8} {:>8} {:>10} {:>10}",
+            event.event,
+            event.entries,
+            format_bytes(event.total_size),
+            event.median_size.map(format_bytes).unwrap_or_default(),
+            event.team_entries,
+            event.solo_entries,
+            event.no_link.len(),
+            event.dead_link.len()
+        );
+    }
+
+    for event in stats {
+        let mut providers: Vec<(&String, &usize)> = event.providers.iter().collect();
+        providers.sort_by_key(|(name, count)| (std::cmp::Reverse(**count), name.as_str()));
+        let others: usize = providers
+            .iter()
+            .skip(TABLE_PROVIDERS)
+            .map(|(_, count)| **count)
+            .sum();
+        let mut providers: Vec<String> = providers
+            .into_iter()
+            .take(TABLE_PROVIDERS)
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        if others > 0 {
+            providers.push(format!("其他 {}", others));
+        }
+        println!();
+        println!("{}: {}", event.event, providers.join(", "));
+        if !event.no_link.is_empty() {
+            println!("  无链接: #{}", event.no_link.join(", #"));
+        }
+        if !event.dead_link.is_empty() {
+            println!("  失效站点: #{}", event.dead_link.join(", #"));
+        }
+    }
+}
+
+pub fn run(args: StatsArgs) -> Result<()> {
+    let files = if args.files.is_empty() {
+        default_event_files()?
+    } else {
+        args.files.clone()
+    };
+
+    let mut stats = Vec::new();
+    for path in &files {
+        match event_stats(path, &args.dead_hosts) {
+            Ok(event) => stats.push(event),
+            Err(e) => warn!("跳过事件文件 {:?}: {}", path, e),
+        }
+    }
+    info!("统计了 {} 个事件文件", stats.len());
+
+    match args.format {
+        StatsFormat::Table => print_table(&stats),
+        StatsFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use indicatif::HumanBytes;
+use serde::Serialize;
+
+/// 隔离目录，未通过验证的文件移到这里
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+#[derive(Debug, Clone, Copy)]
+pub struct VerifyOptions {
+    /// 实际大小与声明大小之差允许的比例 (0.1 为10%)
+    pub size_tolerance: f64,
+}
+
+/// 网盘的错误页、登录页和病毒扫描提示页常被当作文件保存下来
+fn is_html(header: &[u8]) -> bool {
+    let header = header.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(header);
+    let start = header
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(header.len());
+    let text = String::from_utf8_lossy(&header[start..]).to_lowercase();
+    text.starts_with('<')
+        && ["<!doctype html", "<html", "<head", "<body", "<script"]
+            .iter()
+            .any(|tag| text.contains(tag))
+}
+
+/// 检查下载的文件，可疑时返回原因
+pub fn check(
+    path: &Path,
+    declared_size: Option<u64>,
+    options: VerifyOptions,
+) -> Result<Option<String>> {
+    let file = fs::File::open(path).with_context(|| format!("无法打开文件: {:?}", path))?;
+    let size = file
+        .metadata()
+        .with_context(|| format!("无法读取文件: {:?}", path))?
+        .len();
+    if size == 0 {
+        return Ok(Some("文件为空".to_string()));
+    }
+
+    let mut header = Vec::with_capacity(1024);
+    file.take(1024)
+        .read_to_end(&mut header)
+        .with_context(|| format!("无法读取文件: {:?}", path))?;
+    if is_html(&header) {
+        return Ok(Some(
+            "下载到的是HTML页面，可能是错误页或需要登录".to_string(),
+        ));
+    }
+
+    if let Some(declared) = declared_size.filter(|&declared| declared > 0) {
+        let difference = size.abs_diff(declared) as f64;
+        if difference > declared as f64 * options.size_tolerance {
+            return Ok(Some(format!(
+                "文件大小 {} 与声明的大小 {} 相差超过 {:.0}%",
+                HumanBytes(size),
+                HumanBytes(declared),
+                options.size_tolerance * 100.0
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// 隔离文件旁的 "<文件名>.toml"，记录隔离的原因
+#[derive(Debug, Serialize)]
+pub struct QuarantineInfo<'a> {
+    pub no: &'a str,
+    pub link: &'a str,
+    pub reason: &'a str,
+    pub bytes: u64,
+    pub sha256: &'a str,
+    pub time: String,
+}
+
+/// 把文件移到下载目录的 quarantine/ 中，返回新的路径
+pub fn quarantine(path: &Path, output_dir: &Path, info: &QuarantineInfo) -> Result<PathBuf> {
+    let dir = output_dir.join(QUARANTINE_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("创建目录失败: {:?}", dir))?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("无效的文件路径: {:?}", path))?;
+    let target = dir.join(name);
+    fs::rename(path, &target)
+        .with_context(|| format!("重命名文件失败: {:?} -> {:?}", path, target))?;
+
+    let mut info_path = target.as_os_str().to_owned();
+    info_path.push(".toml");
+    let info_path = PathBuf::from(info_path);
+    fs::write(&info_path, toml::to_string_pretty(info)?)
+        .with_context(|| format!("写入文件失败: {:?}", info_path))?;
+    Ok(target)
+}
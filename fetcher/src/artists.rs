@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::{catalog, normalize, write_output};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProfileFormat {
+    Toml,
+    Json,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ArtistsArgs {
+    /// 只输出名称中包含该内容的作者（忽略全角半角、平假名片假名和大小写）
+    #[arg(long)]
+    name: Option<String>,
+
+    /// 作者别名文件，用于合并不同写法或拆分同名作者
+    #[arg(long, default_value = "artists.toml")]
+    aliases: PathBuf,
+
+    /// 输出格式
+    #[arg(short, long, value_enum, default_value = "toml")]
+    format: ProfileFormat,
+
+    /// 输出文件路径，如果不指定则输出到stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// 事件数据目录
+    #[arg(long, default_value = "events")]
+    events_dir: PathBuf,
+
+    /// 事件配置文件路径，用于按年份排序作品
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+}
+
+/// 把多种写法合并为同一个作者
+#[derive(Debug, Deserialize)]
+struct MergeRule {
+    name: String,
+    aliases: Vec<String>,
+}
+
+/// 把某些事件中的同名作者拆分为另一个作者
+#[derive(Debug, Deserialize)]
+struct SplitRule {
+    alias: String,
+    events: Vec<String>,
+    artist: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AliasFile {
+    #[serde(default)]
+    artists: Vec<MergeRule>,
+    #[serde(default)]
+    splits: Vec<SplitRule>,
+}
+
+/// 作者写法到作者的映射
+#[derive(Debug, Default)]
+pub struct ArtistResolver {
+    merges: HashMap<String, String>,
+    splits: HashMap<(String, String), String>,
+}
+
+impl ArtistResolver {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            debug!("别名文件不存在，不使用别名: {:?}", path);
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+        let file: AliasFile =
+            toml::from_str(&content).with_context(|| format!("解析别名文件失败: {:?}", path))?;
+
+        let mut resolver = Self::default();
+        for rule in file.artists {
+            resolver
+                .merges
+                .insert(artist_key(&rule.name), rule.name.clone());
+            for alias in &rule.aliases {
+                resolver.merges.insert(artist_key(alias), rule.name.clone());
+            }
+        }
+        for rule in file.splits {
+            for event in &rule.events {
+                resolver.splits.insert(
+                    (event.clone(), artist_key(&rule.alias)),
+                    rule.artist.clone(),
+                );
+            }
+        }
+        Ok(resolver)
+    }
+
+    /// 返回作者的key和别名文件中指定的显示名（如果有）
+    pub fn resolve(&self, event: &str, name: &str) -> (String, Option<String>) {
+        let key = artist_key(name);
+        if let Some(artist) = self.splits.get(&(event.to_string(), key.clone())) {
+            return (artist_key(artist), Some(artist.clone()));
+        }
+        if let Some(artist) = self.merges.get(&key) {
+            return (artist_key(artist), Some(artist.clone()));
+        }
+        (key, None)
+    }
+}
+
+/// 作者的规范化key：折叠后去掉所有空白
+pub fn artist_key(name: &str) -> String {
+    normalize::fold(name).replace(' ', "")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Work {
+    pub event: String,
+    pub no: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    /// 该作品中使用的作者名
+    pub credited_as: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtistProfile {
+    pub key: String,
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub works: Vec<Work>,
+}
+
+#[derive(Debug, Serialize)]
+struct ArtistIndex {
+    artists: Vec<ArtistProfile>,
+}
+
+/// 汇总所有事件中的作者及其作品，按key排序
+pub fn build_index(
+    events: &[catalog::EventFile],
+    years: &HashMap<String, u16>,
+    resolver: &ArtistResolver,
+) -> Vec<ArtistProfile> {
+    let mut ordered: Vec<&catalog::EventFile> = events.iter().collect();
+    ordered.sort_by_key(|event| (years.get(&event.key).copied(), event.key.clone()));
+
+    let mut profiles: HashMap<String, ArtistProfile> = HashMap::new();
+    let mut display_names: HashMap<String, String> = HashMap::new();
+    let mut alias_counts: HashMap<(String, String), usize> = HashMap::new();
+
+    for event in ordered {
+        for entry in &event.data.entries {
+            // 团队作品按成员分别计入，其他作品按作者栏整体计入
+            let credited: Vec<&str> = if entry.members.is_empty() {
+                vec![entry.name.as_str()]
+            } else {
+                entry.members.iter().map(String::as_str).collect()
+            };
+
+            for name in credited {
+                let (key, display) = resolver.resolve(&event.key, name);
+                if key.is_empty() {
+                    continue;
+                }
+                if let Some(display) = display {
+                    display_names.insert(key.clone(), display);
+                }
+                *alias_counts
+                    .entry((key.clone(), name.to_string()))
+                    .or_default() += 1;
+
+                let profile = profiles
+                    .entry(key.clone())
+                    .or_insert_with(|| ArtistProfile {
+                        key,
+                        name: String::new(),
+                        aliases: Vec::new(),
+                        works: Vec::new(),
+                    });
+                if !profile.aliases.iter().any(|alias| alias == name) {
+                    profile.aliases.push(name.to_string());
+                }
+                profile.works.push(Work {
+                    event: event.key.clone(),
+                    no: entry.no.clone(),
+                    title: entry.title.clone(),
+                    team: entry.team.clone(),
+                    credited_as: entry.name.clone(),
+                    link: catalog::primary_link(entry).map(str::to_string),
+                });
+            }
+        }
+    }
+
+    let mut profiles: Vec<ArtistProfile> = profiles
+        .into_values()
+        .map(|mut profile| {
+            profile.name = display_names
+                .get(&profile.key)
+                .cloned()
+                .unwrap_or_else(|| most_used_alias(&profile, &alias_counts));
+            profile
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.key.cmp(&b.key));
+    profiles
+}
+
+/// 使用次数最多的写法，相同时取最早出现的
+fn most_used_alias(
+    profile: &ArtistProfile,
+    alias_counts: &HashMap<(String, String), usize>,
+) -> String {
+    let mut best: Option<(&str, usize)> = None;
+    for alias in &profile.aliases {
+        let count = alias_counts
+            .get(&(profile.key.clone(), alias.clone()))
+            .copied()
+            .unwrap_or_default();
+        if best.is_none_or(|(_, best_count)| count > best_count) {
+            best = Some((alias, count));
+        }
+    }
+    best.map(|(alias, _)| alias.to_string()).unwrap_or_default()
+}
+
+pub fn run(args: ArtistsArgs) -> Result<()> {
+    let resolver = ArtistResolver::load(&args.aliases)?;
+    let events = catalog::load_event_files(&args.events_dir)?;
+    let keys: Vec<&str> = events.iter().map(|e| e.key.as_str()).collect();
+    let years = catalog::event_years(&args.config, &keys);
+
+    let mut artists = build_index(&events, &years, &resolver);
+    if let Some(name) = &args.name {
+        let needle = artist_key(name);
+        artists.retain(|artist| {
+            artist
+                .aliases
+                .iter()
+                .any(|alias| artist_key(alias).contains(&needle))
+                || artist_key(&artist.name).contains(&needle)
+        });
+    }
+
+    info!("共 {} 位作者", artists.len());
+
+    let index = ArtistIndex { artists };
+    let output = match args.format {
+        ProfileFormat::Toml => toml::to_string_pretty(&index)?,
+        ProfileFormat::Json => serde_json::to_string_pretty(&index)? + "\n",
+    };
+    write_output(&output, &args.output)
+}
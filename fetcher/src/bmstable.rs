@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::{BmsEntry, catalog, charts, feed::xml_escape, impressions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LevelMode {
+    /// 以事件key作为难度
+    Key,
+    /// 以感想分数的排名作为难度
+    Rank,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct BmstableArgs {
+    /// 要导出的事件key，默认导出所有事件
+    events: Vec<String>,
+
+    /// 难度表名称，默认为事件key（多个事件时为 "BOF"）
+    #[arg(long)]
+    name: Option<String>,
+
+    /// 难度表符号，显示在难度前面
+    #[arg(long, default_value = "BOF")]
+    symbol: String,
+
+    /// 难度的取值方式
+    #[arg(long, value_enum, default_value = "key")]
+    level: LevelMode,
+
+    /// 输出目录，生成 index.html、header.json 和 data.json
+    #[arg(short, long, default_value = "bmstable")]
+    output: PathBuf,
+
+    /// 事件数据目录，谱面哈希从 events/<key>/charts.jsonl 读取
+    #[arg(long, default_value = "events")]
+    events_dir: PathBuf,
+
+    /// 事件配置文件路径，用于按年份排序事件
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+}
+
+/// header.json
+#[derive(Debug, Serialize)]
+struct TableHeader {
+    name: String,
+    symbol: String,
+    data_url: String,
+    level_order: Vec<String>,
+}
+
+/// data.json 中的一行，对应一个谱面；没有谱面记录的作品对应一行且不含哈希
+#[derive(Debug, Serialize)]
+struct TableRow {
+    level: String,
+    title: String,
+    artist: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url_diff: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    md5: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    comment: String,
+}
+
+/// 没有感想分数的作品的难度
+const UNRANKED: &str = "?";
+
+/// 按感想分数总和排名，同分同名次；没有分数的作品不在结果中
+fn entry_ranks(events_dir: &Path, key: &str) -> Result<HashMap<String, usize>> {
+    let mut points: HashMap<String, i64> = HashMap::new();
+    for impression in impressions::load_impressions(events_dir, key)? {
+        if let Some(value) = impression.points {
+            *points.entry(impression.no).or_default() += i64::from(value);
+        }
+    }
+    if points.is_empty() {
+        warn!("事件 {} 没有感想分数，所有作品的难度为 {}", key, UNRANKED);
+    }
+
+    let mut ordered: Vec<(String, i64)> = points.into_iter().collect();
+    ordered.sort_by_key(|(no, total)| (std::cmp::Reverse(*total), no.clone()));
+
+    let mut ranks = HashMap::new();
+    let mut previous: Option<(i64, usize)> = None;
+    for (i, (no, total)) in ordered.into_iter().enumerate() {
+        let rank = match previous {
+            Some((previous_total, rank)) if previous_total == total => rank,
+            _ => i + 1,
+        };
+        previous = Some((total, rank));
+        ranks.insert(no, rank);
+    }
+    Ok(ranks)
+}
+
+fn rank_level(key: &str, rank: Option<usize>, multiple: bool) -> String {
+    let rank = rank
+        .map(|rank| rank.to_string())
+        .unwrap_or_else(|| UNRANKED.to_string());
+    if multiple {
+        format!("{} {}", key, rank)
+    } else {
+        rank
+    }
+}
+
+fn entry_rows(
+    key: &str,
+    entry: &BmsEntry,
+    level: &str,
+    entry_charts: &[&charts::ChartRecord],
+) -> Vec<TableRow> {
+    let url = catalog::primary_link(entry).map(str::to_string);
+    let comment = format!("{} #{}", key, entry.no);
+    if entry_charts.is_empty() {
+        return vec![TableRow {
+            level: level.to_string(),
+            title: entry.title.clone(),
+            artist: entry.name.clone(),
+            url,
+            url_diff: None,
+            md5: None,
+            sha256: None,
+            comment,
+        }];
+    }
+
+    entry_charts
+        .iter()
+        .map(|chart| {
+            let title = match (&chart.title, &chart.subtitle) {
+                (Some(title), Some(subtitle)) if !subtitle.is_empty() => {
+                    format!("{} {}", title, subtitle)
+                }
+                (Some(title), _) => title.clone(),
+                (None, _) => entry.title.clone(),
+            };
+            TableRow {
+                level: level.to_string(),
+                title,
+                artist: chart.artist.clone().unwrap_or_else(|| entry.name.clone()),
+                url: url.clone(),
+                url_diff: None,
+                md5: Some(chart.md5.clone()),
+                sha256: Some(chart.sha256.clone()),
+                comment: comment.clone(),
+            }
+        })
+        .collect()
+}
+
+fn render_index(header: &TableHeader, rows: &[TableRow]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"bmstable\" content=\"header.json\">\n");
+    html.push_str(&format!("<title>{}</title>\n", xml_escape(&header.name)));
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", xml_escape(&header.name)));
+    html.push_str("<table>\n<thead><tr><th>Level</th><th>Title</th><th>Artist</th><th>Link</th><th>Comment</th></tr></thead>\n<tbody>\n");
+    for row in rows {
+        let link = row
+            .url
+            .as_ref()
+            .map(|url| {
+                format!(
+                    "<a href=\"{0}\" rel=\"nofollow noopener\">{0}</a>",
+                    xml_escape(url)
+                )
+            })
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<tr><td>{}{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            xml_escape(&header.symbol),
+            xml_escape(&row.level),
+            xml_escape(&row.title),
+            xml_escape(&row.artist),
+            link,
+            xml_escape(&row.comment)
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+pub fn run(args: BmstableArgs) -> Result<()> {
+    let mut events = catalog::load_event_files(&args.events_dir)?;
+    if !args.events.is_empty() {
+        for key in &args.events {
+            if !events.iter().any(|event| &event.key == key) {
+                return Err(anyhow::anyhow!("没有找到事件: {}", key));
+            }
+        }
+        events.retain(|event| args.events.contains(&event.key));
+    }
+    if events.is_empty() {
+        return Err(anyhow::anyhow!("没有可导出的事件"));
+    }
+
+    let keys: Vec<&str> = events.iter().map(|e| e.key.as_str()).collect();
+    let years = catalog::event_years(&args.config, &keys);
+    events.sort_by_key(|event| (years.get(&event.key).copied(), event.key.clone()));
+    let multiple = events.len() > 1;
+
+    let mut rows = Vec::new();
+    let mut level_order: Vec<String> = Vec::new();
+    let mut chart_count = 0;
+    for event in &events {
+        let event_charts = charts::load_charts(&args.events_dir, &event.key)?;
+        chart_count += event_charts.len();
+        let mut charts_by_no: HashMap<&str, Vec<&charts::ChartRecord>> = HashMap::new();
+        for chart in &event_charts {
+            charts_by_no
+                .entry(chart.no.as_str())
+                .or_default()
+                .push(chart);
+        }
+
+        let ranks = match args.level {
+            LevelMode::Key => HashMap::new(),
+            LevelMode::Rank => entry_ranks(&args.events_dir, &event.key)?,
+        };
+
+        let mut event_levels: Vec<(Option<usize>, String)> = Vec::new();
+        for entry in &event.data.entries {
+            let rank = ranks.get(&entry.no).copied();
+            let level = match args.level {
+                LevelMode::Key => event.key.clone(),
+                LevelMode::Rank => rank_level(&event.key, rank, multiple),
+            };
+            if !event_levels.iter().any(|(_, existing)| *existing == level) {
+                event_levels.push((rank, level.clone()));
+            }
+            let entry_charts = charts_by_no
+                .get(entry.no.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            rows.extend(entry_rows(&event.key, entry, &level, entry_charts));
+        }
+
+        // 名次从高到低，没有名次的排在最后
+        event_levels.sort_by_key(|(rank, _)| rank.unwrap_or(usize::MAX));
+        level_order.extend(event_levels.into_iter().map(|(_, level)| level));
+    }
+
+    let header = TableHeader {
+        name: args.name.clone().unwrap_or_else(|| {
+            if multiple {
+                "BOF".to_string()
+            } else {
+                events[0].key.clone()
+            }
+        }),
+        symbol: args.symbol.clone(),
+        data_url: "data.json".to_string(),
+        level_order,
+    };
+
+    fs::create_dir_all(&args.output).with_context(|| format!("创建目录失败: {:?}", args.output))?;
+    let files = [
+        ("header.json", serde_json::to_string_pretty(&header)? + "\n"),
+        ("data.json", serde_json::to_string_pretty(&rows)? + "\n"),
+        ("index.html", render_index(&header, &rows)),
+    ];
+    for (name, content) in files {
+        let path = args.output.join(name);
+        fs::write(&path, content).with_context(|| format!("写入文件失败: {:?}", path))?;
+    }
+
+    info!(
+        "导出了 {} 个事件的 {} 行 (含 {} 个谱面的哈希) -> {:?}",
+        events.len(),
+        rows.len(),
+        chart_count,
+        args.output
+    );
+    Ok(())
+}
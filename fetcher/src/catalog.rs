@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::warn;
+use regex::Regex;
+
+use crate::{BmsData, BmsEntry, load_events_config, parse_event_data};
+
+/// 一个已加载的事件文件 (events/<key>.toml)
+#[derive(Debug)]
+pub struct EventFile {
+    pub key: String,
+    pub path: PathBuf,
+    pub content: String,
+    pub data: BmsData,
+}
+
+/// 列出目录下所有的事件文件，按文件名排序
+pub fn list_event_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("无法读取目录: {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+pub fn load_event_file(path: &Path) -> Result<EventFile> {
+    let key = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow::anyhow!("无效的文件名: {:?}", path))?;
+    let content = fs::read_to_string(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+    let data =
+        parse_event_data(&content).with_context(|| format!("解析TOML文件失败: {:?}", path))?;
+    Ok(EventFile {
+        key,
+        path: path.to_path_buf(),
+        content,
+        data,
+    })
+}
+
+/// 加载目录下所有的事件文件，无法解析的文件会被跳过
+pub fn load_event_files(dir: &Path) -> Result<Vec<EventFile>> {
+    let mut events = Vec::new();
+    for path in list_event_files(dir)? {
+        match load_event_file(&path) {
+            Ok(event) => events.push(event),
+            Err(e) => warn!("跳过事件文件 {:?}: {}", path, e),
+        }
+    }
+    Ok(events)
+}
+
+/// 读取events.toml中各事件的年份，没有 year 字段时从key中的四位数字推断
+pub fn event_years(config_path: &Path, keys: &[&str]) -> HashMap<String, u16> {
+    let mut years: HashMap<String, u16> = match load_events_config(config_path) {
+        Ok(config) => config
+            .events
+            .into_iter()
+            .filter_map(|event| event.year.map(|year| (event.key, year)))
+            .collect(),
+        Err(e) => {
+            warn!("无法读取事件配置 {:?}: {}", config_path, e);
+            HashMap::new()
+        }
+    };
+
+    let year_regex = Regex::new(r"(19|20)\d{2}").unwrap();
+    for key in keys {
+        if years.contains_key(*key) {
+            continue;
+        }
+        if let Some(year) = year_regex
+            .find(key)
+            .and_then(|m| m.as_str().parse::<u16>().ok())
+        {
+            years.insert(key.to_string(), year);
+        }
+    }
+
+    years
+}
+
+/// 作品的主要链接：第一个URL地址
+pub fn primary_link(entry: &BmsEntry) -> Option<&str> {
+    entry
+        .addr
+        .iter()
+        .map(String::as_str)
+        .find(|addr| url_host(addr).is_some())
+}
+
+/// 解析作品大小 (例如 "17000 KB", "1.5 MB")，返回KB数
+pub fn parse_size_kb(size: &str) -> Option<u64> {
+    let normalized = size.trim().replace(',', "").to_uppercase();
+    let split = normalized
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(normalized.len());
+    let (number, unit) = normalized.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let factor = match unit.trim() {
+        "" | "KB" | "K" | "KIB" => 1.0,
+        "MB" | "M" | "MIB" => 1024.0,
+        "GB" | "G" | "GIB" => 1024.0 * 1024.0,
+        "B" | "BYTES" => 1.0 / 1024.0,
+        _ => return None,
+    };
+    Some((number * factor).round() as u64)
+}
+
+/// 从URL中提取主机名 (不含端口)，非URL返回None
+pub fn url_host(addr: &str) -> Option<String> {
+    let rest = addr
+        .strip_prefix("http://")
+        .or_else(|| addr.strip_prefix("https://"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_lowercase())
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// 作品包中的一个谱面，对应 events/<key>/charts.jsonl 中的一行
+///
+/// 由 downloader 的 `inspect` 子命令从下载的作品包中提取
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChartRecord {
+    /// 所属作品的编号
+    pub no: String,
+    /// 谱面文件在作品包中的路径
+    pub file: String,
+    pub md5: String,
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playlevel: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm_min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm_max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<u32>,
+}
+
+pub fn charts_path(events_dir: &Path, key: &str) -> PathBuf {
+    events_dir.join(key).join("charts.jsonl")
+}
+
+/// 读取事件已记录的谱面，没有记录时返回空列表
+pub fn load_charts(events_dir: &Path, key: &str) -> Result<Vec<ChartRecord>> {
+    let path = charts_path(events_dir, key);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path).with_context(|| format!("无法读取文件: {:?}", path))?;
+    let mut charts = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<ChartRecord>(line) {
+            Ok(chart) => charts.push(chart),
+            Err(e) => warn!("跳过无法解析的谱面记录 {:?}:{}: {}", path, i + 1, e),
+        }
+    }
+    Ok(charts)
+}
@@ -0,0 +1,416 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use rusqlite::{Connection, OptionalExtension, params};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    catalog::{self, EventFile},
+    load_events_config,
+};
+
+/// 数据库结构，各表的说明见 fetcher/README.md
+pub const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS events (
+    id            INTEGER PRIMARY KEY,
+    key           TEXT NOT NULL UNIQUE,
+    manbow_id     TEXT,
+    source_path   TEXT NOT NULL,
+    source_sha256 TEXT NOT NULL,
+    imported_at   TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS artists (
+    id   INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS teams (
+    id   INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS entries (
+    id        INTEGER PRIMARY KEY,
+    event_id  INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    position  INTEGER NOT NULL,
+    no        TEXT NOT NULL,
+    artist_id INTEGER NOT NULL REFERENCES artists(id),
+    team_id   INTEGER REFERENCES teams(id),
+    title     TEXT NOT NULL,
+    size      TEXT NOT NULL,
+    size_kb   INTEGER
+);
+CREATE INDEX IF NOT EXISTS entries_event ON entries(event_id);
+CREATE INDEX IF NOT EXISTS entries_artist ON entries(artist_id);
+CREATE INDEX IF NOT EXISTS entries_team ON entries(team_id);
+
+CREATE TABLE IF NOT EXISTS entry_members (
+    entry_id  INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+    position  INTEGER NOT NULL,
+    artist_id INTEGER NOT NULL REFERENCES artists(id),
+    PRIMARY KEY (entry_id, position)
+);
+CREATE INDEX IF NOT EXISTS entry_members_artist ON entry_members(artist_id);
+
+CREATE TABLE IF NOT EXISTS links (
+    id       INTEGER PRIMARY KEY,
+    entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    value    TEXT NOT NULL,
+    is_url   INTEGER NOT NULL,
+    host     TEXT
+);
+CREATE INDEX IF NOT EXISTS links_entry ON links(entry_id);
+CREATE INDEX IF NOT EXISTS links_value ON links(value);
+CREATE INDEX IF NOT EXISTS links_host ON links(host);
+
+CREATE TABLE IF NOT EXISTS link_checks (
+    id         INTEGER PRIMARY KEY,
+    url        TEXT NOT NULL,
+    checked_at TEXT NOT NULL,
+    status     INTEGER,
+    ok         INTEGER NOT NULL,
+    error      TEXT
+);
+CREATE INDEX IF NOT EXISTS link_checks_url ON link_checks(url, checked_at);
+
+CREATE VIEW IF NOT EXISTS entry_details AS
+SELECT
+    en.id      AS entry_id,
+    ev.key     AS event,
+    en.no      AS no,
+    a.name     AS artist,
+    t.name     AS team,
+    en.title   AS title,
+    en.size    AS size,
+    en.size_kb AS size_kb
+FROM entries en
+JOIN events ev ON ev.id = en.event_id
+JOIN artists a ON a.id = en.artist_id
+LEFT JOIN teams t ON t.id = en.team_id;
+
+CREATE VIEW IF NOT EXISTS team_rosters AS
+SELECT DISTINCT
+    ev.key AS event,
+    t.name AS team,
+    a.name AS member
+FROM entry_members m
+JOIN entries en ON en.id = m.entry_id
+JOIN events ev ON ev.id = en.event_id
+JOIN teams t ON t.id = en.team_id
+JOIN artists a ON a.id = m.artist_id;
+"#;
+
+/// 数据库结构版本，结构变化后旧数据库中的所有事件会被重新导入
+const SCHEMA_VERSION: i64 = 2;
+
+#[derive(clap::Args, Debug)]
+pub struct DbArgs {
+    /// 数据库文件路径
+    #[arg(long, default_value = "bof.db")]
+    database: PathBuf,
+
+    /// 事件数据目录
+    #[arg(long, default_value = "events")]
+    events_dir: PathBuf,
+
+    /// 事件配置文件路径，用于记录各事件的event_id
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+
+    /// 即使文件没有变化也重新导入所有事件
+    #[arg(long)]
+    full: bool,
+
+    /// 导入后检查链接是否可以访问，结果写入link_checks表
+    #[arg(long)]
+    check_links: bool,
+
+    /// 最近多少天内检查过的链接不再检查
+    #[arg(long, default_value_t = 7)]
+    check_interval_days: i64,
+
+    /// 检查单个链接的超时时间（秒）
+    #[arg(long, default_value_t = 20)]
+    check_timeout: u64,
+}
+
+pub fn open(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path).with_context(|| format!("无法打开数据库: {:?}", path))?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+fn sha256_hex(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn intern(conn: &Connection, table: &str, name: &str) -> Result<i64> {
+    conn.execute(
+        &format!("INSERT OR IGNORE INTO {} (name) VALUES (?1)", table),
+        params![name],
+    )?;
+    let id = conn.query_row(
+        &format!("SELECT id FROM {} WHERE name = ?1", table),
+        params![name],
+        |row| row.get(0),
+    )?;
+    Ok(id)
+}
+
+fn import_event(
+    conn: &mut Connection,
+    event: &EventFile,
+    manbow_id: Option<&str>,
+    sha256: &str,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+
+    tx.execute("DELETE FROM events WHERE key = ?1", params![event.key])?;
+    tx.execute(
+        "INSERT INTO events (key, manbow_id, source_path, source_sha256, imported_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            event.key,
+            manbow_id,
+            event.path.to_string_lossy(),
+            sha256,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+    let event_row_id = tx.last_insert_rowid();
+
+    for (position, entry) in event.data.entries.iter().enumerate() {
+        let artist_id = intern(&tx, "artists", &entry.name)?;
+        let team_id = entry
+            .team
+            .as_deref()
+            .map(|team| intern(&tx, "teams", team))
+            .transpose()?;
+
+        tx.execute(
+            "INSERT INTO entries (event_id, position, no, artist_id, team_id, title, size, size_kb)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                event_row_id,
+                position as i64,
+                entry.no,
+                artist_id,
+                team_id,
+                entry.title,
+                entry.size,
+                catalog::parse_size_kb(&entry.size).map(|kb| kb as i64),
+            ],
+        )?;
+        let entry_id = tx.last_insert_rowid();
+
+        for (member_position, member) in entry.members.iter().enumerate() {
+            let member_id = intern(&tx, "artists", member)?;
+            tx.execute(
+                "INSERT INTO entry_members (entry_id, position, artist_id) VALUES (?1, ?2, ?3)",
+                params![entry_id, member_position as i64, member_id],
+            )?;
+        }
+
+        for (link_position, addr) in entry.addr.iter().enumerate() {
+            let host = catalog::url_host(addr);
+            tx.execute(
+                "INSERT INTO links (entry_id, position, value, is_url, host)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![entry_id, link_position as i64, addr, host.is_some(), host],
+            )?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+fn remove_orphans(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "DELETE FROM artists
+         WHERE id NOT IN (SELECT artist_id FROM entries)
+           AND id NOT IN (SELECT artist_id FROM entry_members)",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM teams WHERE id NOT IN (SELECT team_id FROM entries WHERE team_id IS NOT NULL)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 把事件目录同步到数据库，只重新导入内容发生变化的文件
+pub fn sync(
+    conn: &mut Connection,
+    events_dir: &Path,
+    manbow_ids: &HashMap<String, String>,
+    full: bool,
+) -> Result<()> {
+    let events = catalog::load_event_files(events_dir)?;
+    // 暂时无法解析的文件也算存在，保留数据库中原有的数据
+    let keys: HashSet<String> = catalog::list_event_files(events_dir)?
+        .iter()
+        .filter_map(|path| path.file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .collect();
+
+    let mut imported = 0;
+    for event in &events {
+        let sha256 = sha256_hex(&event.content);
+        let existing: Option<String> = conn
+            .query_row(
+                "SELECT source_sha256 FROM events WHERE key = ?1",
+                params![event.key],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if !full && existing.as_deref() == Some(sha256.as_str()) {
+            debug!("事件 {} 没有变化，跳过", event.key);
+            continue;
+        }
+
+        import_event(
+            conn,
+            event,
+            manbow_ids.get(&event.key).map(String::as_str),
+            &sha256,
+        )?;
+        info!(
+            "已导入事件 {} ({} 个作品)",
+            event.key,
+            event.data.entries.len()
+        );
+        imported += 1;
+    }
+
+    let stale: Vec<String> = conn
+        .prepare("SELECT key FROM events")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?
+        .into_iter()
+        .filter(|key| !keys.contains(key))
+        .collect();
+    for key in &stale {
+        conn.execute("DELETE FROM events WHERE key = ?1", params![key])?;
+        info!("已删除不存在的事件 {}", key);
+    }
+
+    remove_orphans(conn)?;
+    info!(
+        "同步完成：导入 {} 个事件，删除 {} 个事件",
+        imported,
+        stale.len()
+    );
+    Ok(())
+}
+
+/// 不支持HEAD的服务器常返回这些状态，需要改用GET确认
+const HEAD_UNSUPPORTED: [u16; 3] = [403, 405, 501];
+
+async fn check_link(client: &surf::Client, url: &str) -> (Option<u16>, bool, Option<String>) {
+    match client.head(url).await {
+        Ok(response) => {
+            let status: u16 = response.status().into();
+            if !HEAD_UNSUPPORTED.contains(&status) {
+                return (Some(status), status < 400, None);
+            }
+            debug!("HEAD {} 返回 {}，改用GET重新检查", url, status);
+        }
+        Err(e) => debug!("HEAD {} 失败: {}，改用GET重新检查", url, e),
+    }
+    // 只请求第一个字节，不下载整个文件
+    match client.get(url).header("Range", "bytes=0-0").await {
+        Ok(response) => {
+            let status: u16 = response.status().into();
+            (Some(status), status < 400, None)
+        }
+        Err(e) => (None, false, Some(e.to_string())),
+    }
+}
+
+fn check_links(conn: &Connection, interval_days: i64, timeout: Duration) -> Result<()> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(interval_days)).to_rfc3339();
+    let urls: Vec<String> = conn
+        .prepare(
+            "SELECT DISTINCT value FROM links
+             WHERE is_url = 1
+               AND value NOT IN (SELECT url FROM link_checks WHERE checked_at >= ?1)",
+        )?
+        .query_map(params![since], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    info!("需要检查 {} 个链接", urls.len());
+
+    let client: surf::Client = surf::Config::new()
+        .set_timeout(Some(timeout))
+        .try_into()
+        .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))?;
+    let client = client.with(surf::middleware::Redirect::default());
+
+    for (i, url) in urls.iter().enumerate() {
+        let (status, ok, error) = smol::block_on(check_link(&client, url));
+        if ok {
+            debug!("[{}/{}] {} -> {:?}", i + 1, urls.len(), url, status);
+        } else {
+            warn!(
+                "[{}/{}] 链接不可用 {}: {:?} {}",
+                i + 1,
+                urls.len(),
+                url,
+                status,
+                error.as_deref().unwrap_or_default()
+            );
+        }
+        conn.execute(
+            "INSERT INTO link_checks (url, checked_at, status, ok, error) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![url, chrono::Utc::now().to_rfc3339(), status, ok, error],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn run(args: DbArgs) -> Result<()> {
+    let manbow_ids: HashMap<String, String> = match load_events_config(&args.config) {
+        Ok(config) => config
+            .events
+            .into_iter()
+            .filter_map(|event| event.event_id.map(|id| (event.key, id)))
+            .collect(),
+        Err(e) => {
+            warn!("无法读取事件配置 {:?}: {}", args.config, e);
+            HashMap::new()
+        }
+    };
+
+    let mut conn = open(&args.database)?;
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let full = args.full || version < SCHEMA_VERSION;
+    if full && !args.full {
+        info!("数据库结构已更新，重新导入所有事件");
+    }
+    sync(&mut conn, &args.events_dir, &manbow_ids, full)?;
+    conn.execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION))?;
+
+    if args.check_links {
+        check_links(
+            &conn,
+            args.check_interval_days,
+            Duration::from_secs(args.check_timeout),
+        )?;
+    }
+
+    info!("数据库已更新: {:?}", args.database);
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::BmsEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    New,
+    Updated,
+    Removed,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::New => "new",
+            ChangeKind::Updated => "updated",
+            ChangeKind::Removed => "removed",
+        }
+    }
+}
+
+/// 两次快照之间单个作品的变化
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryChange {
+    pub kind: ChangeKind,
+    pub no: String,
+    /// 变化后的作品，删除时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<BmsEntry>,
+    /// 变化前的作品，新增时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<BmsEntry>,
+}
+
+impl EntryChange {
+    /// 链接列表是否发生了变化（仅对更新有意义）
+    pub fn addr_changed(&self) -> bool {
+        match (&self.entry, &self.previous) {
+            (Some(entry), Some(previous)) => entry.addr != previous.addr,
+            _ => false,
+        }
+    }
+
+    /// 变化后的作品，删除时为变化前的作品
+    pub fn current(&self) -> &BmsEntry {
+        self.entry
+            .as_ref()
+            .or(self.previous.as_ref())
+            .expect("EntryChange 至少包含一个作品")
+    }
+}
+
+/// 按作品序号比较两次快照，结果按新快照中的顺序排列，删除的作品排在最后
+pub fn diff_entries(old: &[BmsEntry], new: &[BmsEntry]) -> Vec<EntryChange> {
+    let old_by_no: HashMap<&str, &BmsEntry> = old.iter().map(|e| (e.no.as_str(), e)).collect();
+    let new_by_no: HashMap<&str, &BmsEntry> = new.iter().map(|e| (e.no.as_str(), e)).collect();
+
+    let mut changes = Vec::new();
+
+    for entry in new {
+        match old_by_no.get(entry.no.as_str()) {
+            None => changes.push(EntryChange {
+                kind: ChangeKind::New,
+                no: entry.no.clone(),
+                entry: Some(entry.clone()),
+                previous: None,
+            }),
+            Some(previous) if *previous != entry => changes.push(EntryChange {
+                kind: ChangeKind::Updated,
+                no: entry.no.clone(),
+                entry: Some(entry.clone()),
+                previous: Some((*previous).clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for entry in old {
+        if !new_by_no.contains_key(entry.no.as_str()) {
+            changes.push(EntryChange {
+                kind: ChangeKind::Removed,
+                no: entry.no.clone(),
+                entry: None,
+                previous: Some(entry.clone()),
+            });
+        }
+    }
+
+    changes
+}
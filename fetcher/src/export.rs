@@ -0,0 +1,147 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use log::{error, info};
+
+use crate::{BmsData, catalog, convert_to_toml};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Toml,
+    Json,
+    Jsonl,
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Toml => "toml",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Csv => "csv",
+        }
+    }
+}
+
+/// CSV中addr列的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AddrMode {
+    /// 所有地址合并到一个单元格，用 " | " 分隔
+    Join,
+    /// 每个地址单独一行，其余列重复
+    Explode,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    /// 要转换的事件文件，默认为 events/ 下的所有 .toml 文件
+    files: Vec<PathBuf>,
+
+    /// 输出格式
+    #[arg(short, long, value_enum, default_value = "json")]
+    format: OutputFormat,
+
+    /// CSV中addr列的处理方式
+    #[arg(long, value_enum, default_value = "join")]
+    addr_mode: AddrMode,
+
+    /// 输出目录，每个事件文件生成一个同名文件
+    #[arg(short, long, default_value = "exports")]
+    output: PathBuf,
+}
+
+const CSV_COLUMNS: [&str; 6] = ["no", "name", "team", "title", "size", "addr"];
+const ADDR_SEPARATOR: &str = " | ";
+
+fn convert_to_csv(data: &BmsData, addr_mode: AddrMode) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_COLUMNS)?;
+
+    for entry in &data.entries {
+        let team = entry.team.as_deref().unwrap_or_default();
+        let fixed = [
+            entry.no.as_str(),
+            entry.name.as_str(),
+            team,
+            entry.title.as_str(),
+            entry.size.as_str(),
+        ];
+        match addr_mode {
+            AddrMode::Join => {
+                let addr = entry.addr.join(ADDR_SEPARATOR);
+                writer.write_record(fixed.iter().copied().chain([addr.as_str()]))?;
+            }
+            AddrMode::Explode if entry.addr.is_empty() => {
+                writer.write_record(fixed.iter().copied().chain([""]))?;
+            }
+            AddrMode::Explode => {
+                for addr in &entry.addr {
+                    writer.write_record(fixed.iter().copied().chain([addr.as_str()]))?;
+                }
+            }
+        }
+    }
+
+    let bytes = writer.into_inner().map_err(|e| anyhow::anyhow!("{}", e))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+fn convert_to_jsonl(data: &BmsData) -> Result<String> {
+    let mut output = String::new();
+    for entry in &data.entries {
+        output.push_str(&serde_json::to_string(entry)?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// 按指定格式序列化事件数据
+pub fn convert(data: &BmsData, format: OutputFormat, addr_mode: AddrMode) -> Result<String> {
+    match format {
+        OutputFormat::Toml => convert_to_toml(data),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(data)? + "\n"),
+        OutputFormat::Jsonl => convert_to_jsonl(data),
+        OutputFormat::Csv => convert_to_csv(data, addr_mode),
+    }
+}
+
+fn export_file(path: &Path, args: &ExportArgs) -> Result<PathBuf> {
+    let event = catalog::load_event_file(path)?;
+    let output_path = args
+        .output
+        .join(&event.key)
+        .with_extension(args.format.extension());
+
+    let output = convert(&event.data, args.format, args.addr_mode)?;
+    fs::write(&output_path, output).with_context(|| format!("写入文件失败: {:?}", output_path))?;
+    Ok(output_path)
+}
+
+pub fn run(args: ExportArgs) -> Result<()> {
+    let files = if args.files.is_empty() {
+        catalog::list_event_files(Path::new("events"))?
+    } else {
+        args.files.clone()
+    };
+
+    if files.is_empty() {
+        error!("没有找到要转换的事件文件");
+        return Ok(());
+    }
+
+    fs::create_dir_all(&args.output).with_context(|| format!("创建目录失败: {:?}", args.output))?;
+
+    for path in &files {
+        match export_file(path, &args) {
+            Ok(output_path) => info!("已转换: {:?} -> {:?}", path, output_path),
+            Err(e) => error!("转换失败 {:?}: {}", path, e),
+        }
+    }
+
+    Ok(())
+}
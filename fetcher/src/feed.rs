@@ -0,0 +1,415 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime};
+use log::{debug, info, warn};
+
+use crate::{
+    BmsEntry,
+    diff::{self, ChangeKind, EntryChange},
+    load_events_config, parse_event_data,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct FeedArgs {
+    /// 只为指定的事件生成订阅 (例如: --event BOFTT --event BOF21)，默认处理events.toml中的所有事件
+    #[arg(long = "event")]
+    events: Vec<String>,
+
+    /// 事件配置文件路径
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+
+    /// 事件数据目录，从git历史中读取该目录下文件的各个版本
+    #[arg(long, default_value = "events")]
+    events_dir: PathBuf,
+
+    /// 快照目录，包含按时间排序的子目录 (例如: snapshots/2025-10-01T1200/BOF21.toml)，指定后不再读取git历史
+    #[arg(long)]
+    snapshots: Option<PathBuf>,
+
+    /// 输出目录，每个事件生成 <key>.atom，并生成汇总的 all.atom
+    #[arg(short, long, default_value = "feeds")]
+    output: PathBuf,
+
+    /// 每个事件最多比较的版本数
+    #[arg(long, default_value_t = 50)]
+    limit: usize,
+
+    /// 订阅中链接使用的站点地址
+    #[arg(long, default_value = "https://github.com/MiyakoMeow/bof-work-info")]
+    base_url: String,
+}
+
+/// 事件文件的某一个历史版本
+struct Version {
+    id: String,
+    updated: DateTime<FixedOffset>,
+    entries: Vec<BmsEntry>,
+}
+
+/// 订阅中的一条记录
+struct FeedItem {
+    id: String,
+    title: String,
+    updated: DateTime<FixedOffset>,
+    content: String,
+}
+
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn parse_entries(content: &str) -> Result<Vec<BmsEntry>> {
+    let data = parse_event_data(content)?;
+    Ok(data.entries)
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("无法执行git命令")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "git {} 执行失败: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 从git历史中读取事件文件的各个版本，按时间从旧到新排列
+fn versions_from_git(path: &Path, limit: usize) -> Result<Vec<Version>> {
+    let path_str = path.to_string_lossy();
+    let log = git(&[
+        "log",
+        "--format=%H%x09%cI",
+        &format!("-n{}", limit + 1),
+        "--",
+        &path_str,
+    ])?;
+
+    // "./" 使 git show 按当前目录而不是仓库根目录解析相对路径
+    let revision_path = if path.is_relative() {
+        format!("./{}", path_str)
+    } else {
+        path_str.to_string()
+    };
+
+    let mut versions = Vec::new();
+    for line in log.lines().rev() {
+        let Some((hash, date)) = line.split_once('\t') else {
+            continue;
+        };
+        let content = match git(&["show", &format!("{}:{}", hash, revision_path)]) {
+            Ok(content) => content,
+            Err(e) => {
+                debug!("跳过版本 {}: {}", hash, e);
+                continue;
+            }
+        };
+        let Ok(updated) = DateTime::parse_from_rfc3339(date) else {
+            warn!("无法解析提交时间 {}: {}", hash, date);
+            continue;
+        };
+        match parse_entries(&content) {
+            Ok(entries) => versions.push(Version {
+                id: hash.to_string(),
+                updated,
+                entries,
+            }),
+            Err(e) => warn!("无法解析版本 {}:{}: {}", hash, path_str, e),
+        }
+    }
+    Ok(versions)
+}
+
+/// 快照目录名中的时间 (例如: 2025-10-01T1200)，没有时区时按本地时间处理
+fn parse_snapshot_time(name: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(name) {
+        return Some(time);
+    }
+    const DATE_TIME_FORMATS: &[&str] = &[
+        "%Y-%m-%dT%H%M%S",
+        "%Y-%m-%dT%H%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H%M",
+        "%Y%m%dT%H%M%S",
+        "%Y%m%dT%H%M",
+    ];
+    const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y%m%d"];
+    let naive = DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(name, format).ok())
+        .or_else(|| {
+            DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(name, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Some(naive.and_local_timezone(Local).earliest()?.fixed_offset())
+}
+
+/// 快照的时间：优先使用目录名，其次为文件在git中最后一次提交的时间，最后才使用修改时间
+fn snapshot_time(snapshot_dir: &Path, path: &Path) -> Result<DateTime<FixedOffset>> {
+    let name = snapshot_dir
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    if let Some(time) = parse_snapshot_time(&name) {
+        return Ok(time);
+    }
+    let committed = git(&["log", "-1", "--format=%cI", "--", &path.to_string_lossy()])
+        .ok()
+        .and_then(|date| DateTime::parse_from_rfc3339(date.trim()).ok());
+    if let Some(time) = committed {
+        return Ok(time);
+    }
+    debug!(
+        "无法从目录名或git历史中得到快照时间，使用修改时间: {:?}",
+        path
+    );
+    let modified: DateTime<Local> = fs::metadata(path)?.modified()?.into();
+    Ok(modified.fixed_offset())
+}
+
+/// 从快照目录中读取事件文件的各个版本，子目录名按字典序视为时间顺序
+fn versions_from_snapshots(dir: &Path, key: &str, limit: usize) -> Result<Vec<Version>> {
+    let mut snapshot_dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("无法读取目录: {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    snapshot_dirs.sort();
+
+    let start = snapshot_dirs.len().saturating_sub(limit + 1);
+    let mut versions = Vec::new();
+    for snapshot_dir in &snapshot_dirs[start..] {
+        let path = snapshot_dir.join(format!("{}.toml", key));
+        if !path.exists() {
+            continue;
+        }
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("无法读取文件: {:?}", path))?;
+        let updated = snapshot_time(snapshot_dir, &path)?;
+        match parse_entries(&content) {
+            Ok(entries) => versions.push(Version {
+                id: snapshot_dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                updated,
+                entries,
+            }),
+            Err(e) => warn!("无法解析快照 {:?}: {}", path, e),
+        }
+    }
+    Ok(versions)
+}
+
+fn change_title(key: &str, change: &EntryChange) -> String {
+    let entry = change.current();
+    let label = match change.kind {
+        ChangeKind::New => "新作品",
+        ChangeKind::Updated if change.addr_changed() => "链接更新",
+        ChangeKind::Updated => "信息更新",
+        ChangeKind::Removed => "作品删除",
+    };
+    format!(
+        "[{}] {} #{} {} - {}",
+        key, label, entry.no, entry.name, entry.title
+    )
+}
+
+fn change_content(change: &EntryChange) -> String {
+    let entry = change.current();
+    let mut lines = vec![
+        format!("作者: {}", entry.name),
+        format!("标题: {}", entry.title),
+    ];
+    if let Some(team) = &entry.team {
+        lines.push(format!("团队: {}", team));
+    }
+    lines.push(format!("大小: {}", entry.size));
+
+    if change.addr_changed() {
+        let previous = change.previous.as_ref().map(|e| &e.addr[..]).unwrap_or(&[]);
+        for addr in previous.iter().filter(|a| !entry.addr.contains(a)) {
+            lines.push(format!("- {}", addr));
+        }
+        for addr in entry.addr.iter().filter(|a| !previous.contains(a)) {
+            lines.push(format!("+ {}", addr));
+        }
+    } else {
+        for addr in &entry.addr {
+            lines.push(format!("链接: {}", addr));
+        }
+    }
+    lines.join("\n")
+}
+
+fn event_items(key: &str, versions: &[Version]) -> Vec<FeedItem> {
+    let mut items = Vec::new();
+    // 第一个版本作为基准，不生成记录
+    for pair in versions.windows(2) {
+        let (previous, current) = (&pair[0], &pair[1]);
+        for change in diff::diff_entries(&previous.entries, &current.entries) {
+            items.push(FeedItem {
+                id: format!(
+                    "urn:bof-work-info:{}:{}:{}:{}",
+                    key,
+                    current.id,
+                    change.no,
+                    change.kind.as_str()
+                ),
+                title: change_title(key, &change),
+                updated: current.updated,
+                content: change_content(&change),
+            });
+        }
+    }
+    items
+}
+
+fn render_feed(id: &str, title: &str, link: &str, items: &[FeedItem]) -> String {
+    let updated = items
+        .iter()
+        .map(|item| item.updated)
+        .max()
+        .unwrap_or_else(|| Local::now().fixed_offset())
+        .to_rfc3339();
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <id>{}</id>\n", xml_escape(id)));
+    xml.push_str(&format!("  <title>{}</title>\n", xml_escape(title)));
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated));
+    xml.push_str(&format!("  <link href=\"{}\"/>\n", xml_escape(link)));
+    xml.push_str("  <author><name>bof-work-info</name></author>\n");
+    for item in items {
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <id>{}</id>\n", xml_escape(&item.id)));
+        xml.push_str(&format!("    <title>{}</title>\n", xml_escape(&item.title)));
+        xml.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            item.updated.to_rfc3339()
+        ));
+        xml.push_str(&format!(
+            "    <content type=\"text\">{}</content>\n",
+            xml_escape(&item.content)
+        ));
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn sort_items(items: &mut [FeedItem]) {
+    // 新的记录在前
+    items.sort_by_key(|item| std::cmp::Reverse(item.updated));
+}
+
+pub fn run(args: FeedArgs) -> Result<()> {
+    let config = load_events_config(&args.config)?;
+    fs::create_dir_all(&args.output).with_context(|| format!("创建目录失败: {:?}", args.output))?;
+
+    let mut all_items = Vec::new();
+    for event in &config.events {
+        if !args.events.is_empty() && !args.events.contains(&event.key) {
+            continue;
+        }
+
+        let versions = match &args.snapshots {
+            Some(dir) => versions_from_snapshots(dir, &event.key, args.limit)?,
+            None => versions_from_git(
+                &args.events_dir.join(format!("{}.toml", event.key)),
+                args.limit,
+            )?,
+        };
+        debug!("事件 {} 读取到 {} 个版本", event.key, versions.len());
+
+        let mut items = event_items(&event.key, &versions);
+        sort_items(&mut items);
+
+        let path = args.output.join(format!("{}.atom", event.key));
+        let feed = render_feed(
+            &format!("urn:bof-work-info:{}", event.key),
+            &format!("{} 作品更新", event.key),
+            &format!("{}/blob/HEAD/events/{}.toml", args.base_url, event.key),
+            &items,
+        );
+        fs::write(&path, feed).with_context(|| format!("写入文件失败: {:?}", path))?;
+        info!(
+            "事件 {} 生成 {} 条记录 -> {:?}",
+            event.key,
+            items.len(),
+            path
+        );
+
+        all_items.extend(items);
+    }
+
+    sort_items(&mut all_items);
+    let path = args.output.join("all.atom");
+    let feed = render_feed(
+        "urn:bof-work-info:all",
+        "BOF 作品更新",
+        &args.base_url,
+        &all_items,
+    );
+    fs::write(&path, feed).with_context(|| format!("写入文件失败: {:?}", path))?;
+    info!("汇总订阅生成 {} 条记录 -> {:?}", all_items.len(), path);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::parse_snapshot_time;
+
+    fn local(name: &str) -> Option<NaiveDateTime> {
+        parse_snapshot_time(name).map(|time| time.naive_local())
+    }
+
+    fn at(hour: u32, min: u32, sec: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(2025, 10, 1).and_then(|date| date.and_hms_opt(hour, min, sec))
+    }
+
+    #[test]
+    fn snapshot_names() {
+        assert_eq!(local("2025-10-01T1200"), at(12, 0, 0));
+        assert_eq!(local("2025-10-01T120530"), at(12, 5, 30));
+        assert_eq!(local("2025-10-01T12:05"), at(12, 5, 0));
+        assert_eq!(local("20251001T1200"), at(12, 0, 0));
+        assert_eq!(local("2025-10-01"), at(0, 0, 0));
+        assert_eq!(local("20251001"), at(0, 0, 0));
+    }
+
+    #[test]
+    fn rfc3339_keeps_offset() {
+        let time = parse_snapshot_time("2025-10-01T12:00:00+09:00").unwrap();
+        assert_eq!(time.offset().local_minus_utc(), 9 * 3600);
+        assert_eq!(time.naive_local(), at(12, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn not_a_time() {
+        assert_eq!(parse_snapshot_time("latest"), None);
+        assert_eq!(parse_snapshot_time("v2"), None);
+        assert_eq!(parse_snapshot_time("2025-13-01"), None);
+    }
+}
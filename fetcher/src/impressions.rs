@@ -0,0 +1,301 @@
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use log::{debug, error, info, warn};
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::{BmsData, EventConfig, clean_html_text, detect_and_decode_content, load_events_config};
+
+#[derive(clap::Args, Debug)]
+pub struct ImpressionsArgs {
+    /// 只处理指定的事件 (例如: --event BOFTT --event BOF21)，默认处理events.toml中的所有事件
+    #[arg(long = "event")]
+    events: Vec<String>,
+
+    /// 事件配置文件路径
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+
+    /// 事件数据目录
+    #[arg(long, default_value = "events")]
+    events_dir: PathBuf,
+
+    /// 每次请求之间的间隔（毫秒）
+    #[arg(long, default_value_t = 1000)]
+    delay_ms: u64,
+
+    /// 忽略上次未完成的进度，从头开始抓取
+    #[arg(long)]
+    restart: bool,
+}
+
+/// 单条感想记录，对应 impressions.jsonl 中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Impression {
+    pub no: String,
+    pub author: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub points: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    pub text: String,
+}
+
+impl Impression {
+    fn dedup_key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.no,
+            self.author,
+            self.timestamp.as_deref().unwrap_or_default(),
+            self.text
+        )
+    }
+}
+
+/// 未完成的抓取进度，全部作品处理完成后删除
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CrawlProgress {
+    completed: Vec<String>,
+}
+
+fn impressions_dir(events_dir: &Path, key: &str) -> PathBuf {
+    events_dir.join(key)
+}
+
+fn entry_page_url(event_id: &str, no: &str) -> String {
+    format!(
+        "https://manbow.nothing.sh/event/event.cgi?action=More_def&num={}&event={}",
+        no, event_id
+    )
+}
+
+/// 读取事件已存档的所有感想，没有存档时返回空列表
+pub fn load_impressions(events_dir: &Path, key: &str) -> Result<Vec<Impression>> {
+    let path = impressions_dir(events_dir, key).join("impressions.jsonl");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path).with_context(|| format!("无法读取文件: {:?}", path))?;
+    let mut impressions = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Impression>(line) {
+            Ok(impression) => impressions.push(impression),
+            Err(e) => warn!("跳过无法解析的感想记录 {:?}:{}: {}", path, i + 1, e),
+        }
+    }
+    Ok(impressions)
+}
+
+fn load_progress(path: &Path) -> Result<CrawlProgress> {
+    if !path.exists() {
+        return Ok(CrawlProgress::default());
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+    toml::from_str(&content).with_context(|| format!("解析进度文件失败: {:?}", path))
+}
+
+fn save_progress(path: &Path, progress: &CrawlProgress) -> Result<()> {
+    let content = toml::to_string_pretty(progress)?;
+    fs::write(path, content).with_context(|| format!("保存进度文件失败: {:?}", path))
+}
+
+/// 从作品详情页中解析感想
+///
+/// 详情页的感想以表格行的形式出现，每行包含作者、分数、时间和正文。
+/// 列的顺序在不同年代的页面中并不固定，因此按内容推断：
+/// 含有时间的行才被视为感想，纯数字的单元格视为分数，
+/// 剩余单元格中第一个为作者，最长的为正文。
+pub fn parse_impressions(no: &str, html: &str) -> Vec<Impression> {
+    let document = Html::parse_document(html);
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("td").unwrap();
+    let date_regex = Regex::new(r"\d{4}[/-]\d{1,2}[/-]\d{1,2}[ T]\d{1,2}:\d{2}(:\d{2})?").unwrap();
+    let points_regex = Regex::new(r"^([+-]?\d+)\s*(pts?|点)?$").unwrap();
+
+    let mut impressions = Vec::new();
+
+    for row in document.select(&row_selector) {
+        let cells: Vec<String> = row.select(&cell_selector).map(clean_html_text).collect();
+        if cells.len() < 2 {
+            continue;
+        }
+
+        let Some(timestamp) = cells
+            .iter()
+            .find_map(|cell| date_regex.find(cell).map(|m| m.as_str().to_string()))
+        else {
+            continue;
+        };
+
+        let mut points = None;
+        let mut rest = Vec::new();
+        for cell in &cells {
+            let text = cell.replace(&timestamp, "").trim().to_string();
+            if text.is_empty() {
+                continue;
+            }
+            if points.is_none()
+                && let Some(caps) = points_regex.captures(&text)
+            {
+                points = caps[1].parse::<i32>().ok();
+                continue;
+            }
+            rest.push(text);
+        }
+
+        if rest.len() < 2 {
+            continue;
+        }
+
+        let author = rest.remove(0);
+        let text = rest
+            .into_iter()
+            .max_by_key(|s| s.chars().count())
+            .unwrap_or_default();
+
+        impressions.push(Impression {
+            no: no.to_string(),
+            author,
+            points,
+            timestamp: Some(timestamp),
+            text,
+        });
+    }
+
+    impressions
+}
+
+async fn fetch_impressions(url: &str, no: &str) -> Result<Vec<Impression>> {
+    debug!("正在获取作品详情页: {}", url);
+    let mut response = surf::get(url)
+        .await
+        .map_err(|e| anyhow::anyhow!("HTTP请求失败: {}", e))?;
+    let response_bytes = response
+        .body_bytes()
+        .await
+        .map_err(|e| anyhow::anyhow!("读取响应失败: {}", e))?;
+
+    let html_content = detect_and_decode_content(&response_bytes);
+    Ok(parse_impressions(no, &html_content))
+}
+
+async fn crawl_event(event: &EventConfig, args: &ImpressionsArgs) -> Result<()> {
+    let Some(event_id) = &event.event_id else {
+        warn!("事件 {} 没有 event_id，无法抓取感想", event.key);
+        return Ok(());
+    };
+
+    let entries_path = args.events_dir.join(format!("{}.toml", event.key));
+    let content = fs::read_to_string(&entries_path)
+        .with_context(|| format!("无法读取文件: {:?}", entries_path))?;
+    let data: BmsData = toml::from_str(&content)
+        .with_context(|| format!("解析TOML文件失败: {:?}", entries_path))?;
+
+    let dir = impressions_dir(&args.events_dir, &event.key);
+    fs::create_dir_all(&dir).with_context(|| format!("创建目录失败: {:?}", dir))?;
+    let output_path = dir.join("impressions.jsonl");
+    let progress_path = dir.join("impressions.progress.toml");
+
+    let mut seen: HashSet<String> = load_impressions(&args.events_dir, &event.key)?
+        .iter()
+        .map(Impression::dedup_key)
+        .collect();
+    let mut progress = if args.restart {
+        CrawlProgress::default()
+    } else {
+        load_progress(&progress_path)?
+    };
+    if !progress.completed.is_empty() {
+        info!(
+            "事件 {} 从上次进度继续，已完成 {} 个作品",
+            event.key,
+            progress.completed.len()
+        );
+    }
+
+    let mut output = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&output_path)
+        .with_context(|| format!("无法打开文件: {:?}", output_path))?;
+
+    let mut added = 0;
+    for entry in &data.entries {
+        if progress.completed.contains(&entry.no) {
+            continue;
+        }
+
+        let url = entry_page_url(event_id, &entry.no);
+        match fetch_impressions(&url, &entry.no).await {
+            Ok(impressions) => {
+                let mut new_count = 0;
+                for impression in impressions {
+                    if !seen.insert(impression.dedup_key()) {
+                        continue;
+                    }
+                    writeln!(output, "{}", serde_json::to_string(&impression)?)
+                        .with_context(|| format!("写入文件失败: {:?}", output_path))?;
+                    new_count += 1;
+                }
+                debug!("作品 #{} 新增 {} 条感想", entry.no, new_count);
+                added += new_count;
+
+                progress.completed.push(entry.no.clone());
+                save_progress(&progress_path, &progress)?;
+            }
+            Err(e) => {
+                // 失败的作品不记录进度，下次运行时重试
+                error!("获取作品 #{} 的感想失败: {}", entry.no, e);
+            }
+        }
+
+        smol::Timer::after(Duration::from_millis(args.delay_ms)).await;
+    }
+
+    if progress.completed.len() >= data.entries.len() {
+        let _ = fs::remove_file(&progress_path);
+    }
+
+    info!(
+        "事件 {} 感想抓取完成，新增 {} 条 -> {:?}",
+        event.key, added, output_path
+    );
+    Ok(())
+}
+
+pub async fn run(args: ImpressionsArgs) -> Result<()> {
+    let config = load_events_config(&args.config)?;
+
+    let events: Vec<&EventConfig> = config
+        .events
+        .iter()
+        .filter(|event| args.events.is_empty() || args.events.contains(&event.key))
+        .collect();
+
+    if events.is_empty() {
+        error!("没有找到要处理的事件");
+        return Ok(());
+    }
+
+    for event in events {
+        info!("开始抓取事件 {} 的感想", event.key);
+        if let Err(e) = crawl_event(event, &args).await {
+            error!("抓取事件 {} 的感想失败: {}", event.key, e);
+        }
+    }
+
+    Ok(())
+}
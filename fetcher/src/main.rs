@@ -1,23 +1,42 @@
 use std::{
     collections::HashMap,
     io::{self, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use encoding_rs::{EUC_JP, SHIFT_JIS, UTF_8};
 use log::{debug, error, info};
 use regex::Regex;
 use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+mod artists;
+mod bmstable;
+mod catalog;
+mod charts;
+mod db;
+mod diff;
+mod export;
+mod feed;
+mod impressions;
+mod members;
+mod normalize;
+mod search;
+mod serve;
+mod site;
+mod watch;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct BmsEntry {
     no: String,
     name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     team: Option<String>,
+    /// 团队作品的成员列表，由 `name` 拆分得到
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    members: Vec<String>,
     title: String,
     size: String,
     addr: Vec<String>,
@@ -35,6 +54,8 @@ struct EventConfig {
     event_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    year: Option<u16>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -43,8 +64,11 @@ struct EventsConfig {
 }
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// 输出文件路径，如果不指定则输出到stdout
     #[arg(short, long)]
     output: Option<PathBuf>,
@@ -53,11 +77,43 @@ struct Args {
     #[arg(long)]
     stdin: bool,
 
+    /// 输出格式
+    #[arg(short, long, value_enum, default_value = "toml")]
+    format: export::OutputFormat,
+
+    /// CSV中addr列的处理方式
+    #[arg(long, value_enum, default_value = "join")]
+    addr_mode: export::AddrMode,
+
     /// 日志级别 (trace, debug, info, warn, error)
-    #[arg(long, default_value = "info")]
+    #[arg(long, default_value = "info", global = true)]
     log_level: String,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 抓取各作品的感想并存档到 events/<key>/impressions.jsonl
+    Impressions(impressions::ImpressionsArgs),
+    /// 定时重新抓取事件页面，输出新增、更新和删除的作品
+    Watch(watch::WatchArgs),
+    /// 比较事件文件的历史版本，生成Atom订阅
+    Feed(feed::FeedArgs),
+    /// 将已有的 events/*.toml 转换为 JSON、JSON Lines 或 CSV
+    Export(export::ExportArgs),
+    /// 把所有 events/*.toml 导入SQLite数据库，文件变化时增量更新
+    Db(db::DbArgs),
+    /// 在所有事件中搜索作者、团队和标题（忽略全角半角、平假名片假名和大小写）
+    Search(search::SearchArgs),
+    /// 按作者汇总所有事件中的作品
+    Artists(artists::ArtistsArgs),
+    /// 生成可部署到任意静态文件服务器的HTML站点
+    Site(site::SiteArgs),
+    /// 启动只读的HTTP JSON接口，事件文件变化时自动重新加载
+    Serve(serve::ServeArgs),
+    /// 导出为 beatoraja/LR2 可读取的难度表 (header.json, data.json)
+    Bmstable(bmstable::BmstableArgs),
+}
+
 #[derive(Debug, Clone)]
 struct ColumnMapping {
     no: Option<usize>,
@@ -437,10 +493,17 @@ async fn fetch_and_parse_table(url: &str) -> Result<BmsData> {
             .filter(|s| !s.trim().is_empty())
             .collect();
 
+        let members = if team_text.is_some() {
+            members::split_members(&name_text)
+        } else {
+            Vec::new()
+        };
+
         let entry = BmsEntry {
             no: no_clean,
             name: name_text,
             team: team_text,
+            members,
             title: title_text,
             size: size_text,
             addr: addr_lines,
@@ -455,6 +518,13 @@ async fn fetch_and_parse_table(url: &str) -> Result<BmsData> {
     Ok(BmsData { entries })
 }
 
+/// 解析事件文件，并为旧文件中的团队作品补全成员列表
+fn parse_event_data(content: &str) -> Result<BmsData> {
+    let mut data: BmsData = toml::from_str(content)?;
+    members::fill_members(&mut data.entries);
+    Ok(data)
+}
+
 fn convert_to_toml(data: &BmsData) -> Result<String> {
     debug!("正在转换为TOML格式...");
     let toml_string = toml::to_string_pretty(data)?;
@@ -476,23 +546,32 @@ fn read_urls_from_stdin() -> Result<Vec<String>> {
     Ok(urls)
 }
 
-fn read_events_from_file(path: &PathBuf) -> Result<Vec<String>> {
+fn load_events_config(path: &Path) -> Result<EventsConfig> {
     debug!("从events.toml读取事件配置: {:?}", path);
     let content = std::fs::read_to_string(path)?;
     let config: EventsConfig = toml::from_str(&content)?;
+    Ok(config)
+}
+
+fn event_url(event: &EventConfig) -> Option<String> {
+    if let Some(event_id) = &event.event_id {
+        // 使用 event_id 构建 URL
+        Some(format!(
+            "https://manbow.nothing.sh/event/event.cgi?action=URLList&end=999&event={}",
+            event_id
+        ))
+    } else {
+        // 使用现有的 url 字段（向后兼容）
+        event.url.clone()
+    }
+}
+
+fn read_events_from_file(path: &Path) -> Result<Vec<String>> {
+    let config = load_events_config(path)?;
 
     let mut urls = Vec::new();
-    for event in config.events {
-        let url = if let Some(event_id) = event.event_id {
-            // 使用 event_id 构建 URL
-            format!(
-                "https://manbow.nothing.sh/event/event.cgi?action=URLList&end=999&event={}",
-                event_id
-            )
-        } else if let Some(url) = event.url {
-            // 使用现有的 url 字段（向后兼容）
-            url
-        } else {
+    for event in &config.events {
+        let Some(url) = event_url(event) else {
             error!("事件 {} 既没有 event_id 也没有 url 字段", event.key);
             continue;
         };
@@ -533,7 +612,21 @@ fn main() -> Result<()> {
         })
         .init();
 
-    smol::block_on(async_main(args))
+    match args.command {
+        Some(Command::Impressions(impressions_args)) => {
+            smol::block_on(impressions::run(impressions_args))
+        }
+        Some(Command::Watch(watch_args)) => smol::block_on(watch::run(watch_args)),
+        Some(Command::Feed(feed_args)) => feed::run(feed_args),
+        Some(Command::Export(export_args)) => export::run(export_args),
+        Some(Command::Db(db_args)) => db::run(db_args),
+        Some(Command::Search(search_args)) => search::run(search_args),
+        Some(Command::Artists(artists_args)) => artists::run(artists_args),
+        Some(Command::Site(site_args)) => site::run(site_args),
+        Some(Command::Serve(serve_args)) => serve::run(serve_args),
+        Some(Command::Bmstable(bmstable_args)) => bmstable::run(bmstable_args),
+        None => smol::block_on(async_main(args)),
+    }
 }
 
 async fn async_main(args: Args) -> Result<()> {
@@ -580,12 +673,12 @@ async fn async_main(args: Args) -> Result<()> {
         entries: all_entries,
     };
 
-    match convert_to_toml(&bms_data) {
-        Ok(toml_output) => {
-            write_output(&toml_output, &args.output)?;
+    match export::convert(&bms_data, args.format, args.addr_mode) {
+        Ok(output) => {
+            write_output(&output, &args.output)?;
         }
         Err(e) => {
-            error!("转换为TOML时出错: {}", e);
+            error!("转换为{:?}格式时出错: {}", args.format, e);
         }
     }
 
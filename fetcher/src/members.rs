@@ -0,0 +1,92 @@
+use regex::Regex;
+
+use crate::BmsEntry;
+
+/// 把团队作品的作者栏拆分为成员列表
+///
+/// 团队活动中 `name` 经常包含多位成员，用 `/`、`&`、`×`、`x`、`+`、`vs.` 或 `feat.` 等分隔，
+/// 例如 `MiKaDo feat. はらもりよしな` 拆分为 `["MiKaDo", "はらもりよしな"]`。
+/// 括号内的分隔符不拆分，例如 `虹色鴉（ume/a-m）` 保持为一个成员。
+pub fn split_members(name: &str) -> Vec<String> {
+    let separator =
+        Regex::new(r"(?i)\s*(?:/|／|&|＆|×|、|\+\s|＋\s|\sx\s|\svs\.?\s|\bfeat\.|\bft\.)\s*")
+            .unwrap();
+
+    let mut members = Vec::new();
+    let mut last_end = 0;
+    for mat in separator.find_iter(name) {
+        if bracket_depth(&name[..mat.start()]) > 0 {
+            continue;
+        }
+        members.push(&name[last_end..mat.start()]);
+        last_end = mat.end();
+    }
+    members.push(&name[last_end..]);
+
+    members
+        .into_iter()
+        .map(str::trim)
+        .filter(|member| !member.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn bracket_depth(text: &str) -> i32 {
+    text.chars().fold(0, |depth, c| match c {
+        '(' | '（' | '[' | '［' | '【' | '「' => depth + 1,
+        ')' | '）' | ']' | '］' | '】' | '」' => (depth - 1).max(0),
+        _ => depth,
+    })
+}
+
+/// 为团队作品补全成员列表，已有成员列表或没有团队的作品保持不变
+pub fn fill_members(entries: &mut [BmsEntry]) {
+    for entry in entries {
+        if entry.members.is_empty() && entry.team.is_some() {
+            entry.members = split_members(&entry.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_members;
+
+    #[test]
+    fn separators() {
+        assert_eq!(split_members("A / B"), ["A", "B"]);
+        assert_eq!(split_members("A／B"), ["A", "B"]);
+        assert_eq!(split_members("A & B＆C"), ["A", "B", "C"]);
+        assert_eq!(split_members("A×B"), ["A", "B"]);
+        assert_eq!(split_members("A x B"), ["A", "B"]);
+        assert_eq!(split_members("A + B"), ["A", "B"]);
+        assert_eq!(split_members("A vs. B"), ["A", "B"]);
+        assert_eq!(split_members("A、B"), ["A", "B"]);
+    }
+
+    #[test]
+    fn featuring() {
+        assert_eq!(
+            split_members("MiKaDo feat. はらもりよしな"),
+            ["MiKaDo", "はらもりよしな"]
+        );
+        assert_eq!(split_members("A Feat.B"), ["A", "B"]);
+        assert_eq!(split_members("A ft. B / C"), ["A", "B", "C"]);
+    }
+
+    #[test]
+    fn brackets_not_split() {
+        assert_eq!(split_members("虹色鴉（ume/a-m）"), ["虹色鴉（ume/a-m）"]);
+        assert_eq!(split_members("X (A & B) / Y"), ["X (A & B)", "Y"]);
+        assert_eq!(split_members("【A×B】"), ["【A×B】"]);
+    }
+
+    #[test]
+    fn no_separator() {
+        // 名字中的 x 和 + 没有空格时不是分隔符
+        assert_eq!(split_members("Xenon"), ["Xenon"]);
+        assert_eq!(split_members("maxx"), ["maxx"]);
+        assert_eq!(split_members("C+"), ["C+"]);
+        assert_eq!(split_members(" / "), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,54 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// 把文本折叠为便于比较的形式
+///
+/// - NFKC规范化：全角英数字和半角片假名统一为标准形式
+/// - 片假名转换为平假名
+/// - 大小写折叠
+/// - 连续空白合并为一个空格
+pub fn fold(text: &str) -> String {
+    let folded: String = text
+        .nfkc()
+        .map(katakana_to_hiragana)
+        .flat_map(char::to_lowercase)
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn katakana_to_hiragana(c: char) -> char {
+    match c {
+        // ァ..ヶ 与 ぁ..ゖ 一一对应；ヽヾ 对应 ゝゞ
+        '\u{30A1}'..='\u{30F6}' | '\u{30FD}'..='\u{30FE}' => {
+            char::from_u32(c as u32 - 0x60).unwrap_or(c)
+        }
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fold;
+
+    #[test]
+    fn full_width() {
+        assert_eq!(fold("ＢＯＦ２０２３"), "bof2023");
+        assert_eq!(fold("ｶﾀｶﾅ"), "かたかな");
+        assert_eq!(fold("Ａ　Ｂ"), "a b");
+    }
+
+    #[test]
+    fn katakana_to_hiragana() {
+        assert_eq!(fold("カタカナ"), "かたかな");
+        assert_eq!(fold("ヴァイオリン"), "ゔぁいおりん");
+        assert_eq!(fold("ヽヾ"), "ゝゞ");
+        // 长音符号和中点不是假名，保持不变
+        assert_eq!(fold("ラーメン・ライス"), "らーめん・らいす");
+    }
+
+    #[test]
+    fn case_and_whitespace() {
+        assert_eq!(fold("Hello World"), "hello world");
+        assert_eq!(fold("  ΣIGMA \t Straße  "), "σigma straße");
+        assert_eq!(fold("MiKaDo"), fold("mikado"));
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use log::info;
+
+use crate::{BmsEntry, catalog, normalize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SearchField {
+    Name,
+    Team,
+    Title,
+}
+
+impl SearchField {
+    pub const ALL: [SearchField; 3] = [SearchField::Name, SearchField::Team, SearchField::Title];
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SearchArgs {
+    /// 搜索内容，多个词之间用空格分隔，所有词都需要匹配
+    query: String,
+
+    /// 只搜索指定的事件 (例如: --event BOFTT --event BOF21)
+    #[arg(long = "event")]
+    events: Vec<String>,
+
+    /// 只搜索指定的字段，默认搜索所有字段
+    #[arg(long = "field", value_enum)]
+    fields: Vec<SearchField>,
+
+    /// 只搜索指定年份的事件
+    #[arg(long = "year")]
+    years: Vec<u16>,
+
+    /// 事件数据目录
+    #[arg(long, default_value = "events")]
+    events_dir: PathBuf,
+
+    /// 事件配置文件路径，用于读取事件年份
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+}
+
+fn field_values<'a>(entry: &'a BmsEntry, fields: &[SearchField]) -> Vec<&'a str> {
+    fields
+        .iter()
+        .filter_map(|field| match field {
+            SearchField::Name => Some(entry.name.as_str()),
+            SearchField::Team => entry.team.as_deref(),
+            SearchField::Title => Some(entry.title.as_str()),
+        })
+        .collect()
+}
+
+/// 所有搜索词都出现在任意一个字段中时视为匹配
+pub fn matches(entry: &BmsEntry, terms: &[String], fields: &[SearchField]) -> bool {
+    let haystacks: Vec<String> = field_values(entry, fields)
+        .into_iter()
+        .map(normalize::fold)
+        .collect();
+    terms
+        .iter()
+        .all(|term| haystacks.iter().any(|haystack| haystack.contains(term)))
+}
+
+/// 把搜索内容折叠后按空格拆分为搜索词
+pub fn parse_terms(query: &str) -> Vec<String> {
+    normalize::fold(query)
+        .split(' ')
+        .filter(|term| !term.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn run(args: SearchArgs) -> Result<()> {
+    let terms = parse_terms(&args.query);
+    if terms.is_empty() {
+        return Err(anyhow::anyhow!("搜索内容不能为空"));
+    }
+
+    let fields = if args.fields.is_empty() {
+        SearchField::ALL.to_vec()
+    } else {
+        args.fields.clone()
+    };
+
+    let events = catalog::load_event_files(&args.events_dir)?;
+    let keys: Vec<&str> = events.iter().map(|e| e.key.as_str()).collect();
+    let years = catalog::event_years(&args.config, &keys);
+
+    let mut count = 0;
+    for event in &events {
+        if !args.events.is_empty() && !args.events.contains(&event.key) {
+            continue;
+        }
+        if !args.years.is_empty()
+            && !years
+                .get(&event.key)
+                .is_some_and(|year| args.years.contains(year))
+        {
+            continue;
+        }
+
+        for entry in &event.data.entries {
+            if !matches(entry, &terms, &fields) {
+                continue;
+            }
+            let team = entry
+                .team
+                .as_deref()
+                .map(|team| format!(" [{}]", team))
+                .unwrap_or_default();
+            println!(
+                "{}\t#{}\t{}{} - {}\t{}",
+                event.key,
+                entry.no,
+                entry.name,
+                team,
+                entry.title,
+                catalog::primary_link(entry).unwrap_or("-")
+            );
+            count += 1;
+        }
+    }
+
+    info!("找到 {} 个作品", count);
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    BmsEntry, catalog,
+    search::{self, SearchField},
+};
+
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    /// 监听地址
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// 事件数据目录
+    #[arg(long, default_value = "events")]
+    events_dir: PathBuf,
+
+    /// 事件配置文件路径，用于读取事件年份
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+
+    /// 检查事件文件是否变化的最小间隔（秒）
+    #[arg(long, default_value_t = 2)]
+    reload_interval: u64,
+}
+
+/// 文件路径、修改时间和大小，任意一项变化时重新加载
+type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+/// 内存中的事件数据，事件文件变化时自动重新加载
+struct Catalogue {
+    events: Vec<catalog::EventFile>,
+    years: HashMap<String, u16>,
+    fingerprint: Fingerprint,
+    checked_at: Instant,
+}
+
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EventSummary<'a> {
+    key: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    year: Option<u16>,
+    entries: usize,
+}
+
+#[derive(Serialize)]
+struct EventDetail<'a> {
+    key: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    year: Option<u16>,
+    entries: &'a [BmsEntry],
+}
+
+#[derive(Serialize)]
+struct SearchHit<'a> {
+    event: &'a str,
+    #[serde(flatten)]
+    entry: &'a BmsEntry,
+}
+
+#[derive(Serialize)]
+struct SearchResult<'a> {
+    total: usize,
+    hits: Vec<SearchHit<'a>>,
+}
+
+fn fingerprint(args: &ServeArgs) -> Result<Fingerprint> {
+    let mut paths = catalog::list_event_files(&args.events_dir)?;
+    paths.push(args.config.clone());
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let metadata = fs::metadata(&path).ok();
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            let len = metadata.map(|m| m.len()).unwrap_or_default();
+            (path, modified, len)
+        })
+        .collect())
+}
+
+impl Catalogue {
+    fn load(args: &ServeArgs) -> Result<Self> {
+        let fingerprint = fingerprint(args)?;
+        let events = catalog::load_event_files(&args.events_dir)?;
+        let keys: Vec<&str> = events.iter().map(|e| e.key.as_str()).collect();
+        let years = catalog::event_years(&args.config, &keys);
+        info!("加载了 {} 个事件", events.len());
+        Ok(Self {
+            events,
+            years,
+            fingerprint,
+            checked_at: Instant::now(),
+        })
+    }
+
+    /// 距上次检查超过间隔时检查文件是否变化，变化则重新加载
+    fn refresh(&mut self, args: &ServeArgs) {
+        if self.checked_at.elapsed() < Duration::from_secs(args.reload_interval) {
+            return;
+        }
+        self.checked_at = Instant::now();
+        match fingerprint(args) {
+            Ok(current) if current == self.fingerprint => {}
+            Ok(_) => {
+                info!("事件文件已变化，重新加载");
+                match Self::load(args) {
+                    Ok(catalogue) => *self = catalogue,
+                    Err(e) => error!("重新加载失败，继续使用旧数据: {}", e),
+                }
+            }
+            Err(e) => warn!("无法检查事件文件: {}", e),
+        }
+    }
+
+    fn event(&self, key: &str) -> Result<&catalog::EventFile, ApiError> {
+        self.events
+            .iter()
+            .find(|event| event.key == key)
+            .ok_or_else(|| ApiError::new(404, format!("没有找到事件: {}", key)))
+    }
+}
+
+/// 解析查询参数，同名参数可以出现多次
+fn query_params(query: &str) -> Vec<(String, String)> {
+    url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// 同名参数的所有值，值中的逗号视为分隔符
+fn param_values(params: &[(String, String)], name: &str) -> Vec<String> {
+    params
+        .iter()
+        .filter(|(key, _)| key == name)
+        .flat_map(|(_, value)| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn search<'a>(
+    catalogue: &'a Catalogue,
+    params: &[(String, String)],
+) -> Result<SearchResult<'a>, ApiError> {
+    let query = params
+        .iter()
+        .find(|(key, _)| key == "q")
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_default();
+    let terms = search::parse_terms(query);
+    if terms.is_empty() {
+        return Err(ApiError::new(400, "缺少搜索内容 (q)"));
+    }
+
+    let fields = param_values(params, "field")
+        .iter()
+        .map(|field| {
+            SearchField::from_str(field, true)
+                .map_err(|_| ApiError::new(400, format!("无效的字段: {}", field)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let fields = if fields.is_empty() {
+        SearchField::ALL.to_vec()
+    } else {
+        fields
+    };
+    let events = param_values(params, "event");
+    let years = param_values(params, "year")
+        .iter()
+        .map(|year| {
+            year.parse::<u16>()
+                .map_err(|_| ApiError::new(400, format!("无效的年份: {}", year)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let limit = match param_values(params, "limit").first() {
+        Some(limit) => limit
+            .parse::<usize>()
+            .map_err(|_| ApiError::new(400, format!("无效的数量: {}", limit)))?,
+        None => 100,
+    };
+
+    let mut total = 0;
+    let mut hits = Vec::new();
+    for event in &catalogue.events {
+        if !events.is_empty() && !events.contains(&event.key) {
+            continue;
+        }
+        if !years.is_empty()
+            && !catalogue
+                .years
+                .get(&event.key)
+                .is_some_and(|year| years.contains(year))
+        {
+            continue;
+        }
+        for entry in &event.data.entries {
+            if search::matches(entry, &terms, &fields) {
+                total += 1;
+                if hits.len() < limit {
+                    hits.push(SearchHit {
+                        event: &event.key,
+                        entry,
+                    });
+                }
+            }
+        }
+    }
+    Ok(SearchResult { total, hits })
+}
+
+fn route(catalogue: &Catalogue, path: &str, query: &str) -> Result<String, ApiError> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let json = match segments.as_slice() {
+        [] => serde_json::json!({
+            "endpoints": [
+                "/events",
+                "/events/{key}",
+                "/events/{key}/entries",
+                "/events/{key}/entries/{no}",
+                "/search?q=",
+            ]
+        })
+        .to_string(),
+        ["events"] => {
+            let summaries: Vec<EventSummary> = catalogue
+                .events
+                .iter()
+                .map(|event| EventSummary {
+                    key: &event.key,
+                    year: catalogue.years.get(&event.key).copied(),
+                    entries: event.data.entries.len(),
+                })
+                .collect();
+            serde_json::to_string(&summaries).map_err(|e| ApiError::new(500, e.to_string()))?
+        }
+        ["events", key] => {
+            let event = catalogue.event(key)?;
+            serde_json::to_string(&EventDetail {
+                key: &event.key,
+                year: catalogue.years.get(&event.key).copied(),
+                entries: &event.data.entries,
+            })
+            .map_err(|e| ApiError::new(500, e.to_string()))?
+        }
+        ["events", key, "entries"] => {
+            let event = catalogue.event(key)?;
+            serde_json::to_string(&event.data.entries)
+                .map_err(|e| ApiError::new(500, e.to_string()))?
+        }
+        ["events", key, "entries", no] => {
+            let event = catalogue.event(key)?;
+            let entry = event
+                .data
+                .entries
+                .iter()
+                .find(|entry| entry.no == *no)
+                .ok_or_else(|| ApiError::new(404, format!("事件 {} 中没有作品 #{}", key, no)))?;
+            serde_json::to_string(entry).map_err(|e| ApiError::new(500, e.to_string()))?
+        }
+        ["search"] => serde_json::to_string(&search(catalogue, &query_params(query))?)
+            .map_err(|e| ApiError::new(500, e.to_string()))?,
+        _ => return Err(ApiError::new(404, format!("没有这个接口: {}", path))),
+    };
+    Ok(json)
+}
+
+fn respond(request: Request, status: u16, body: String) {
+    let headers = [
+        "Content-Type: application/json; charset=utf-8",
+        "Access-Control-Allow-Origin: *",
+    ];
+    let mut response = Response::from_string(body).with_status_code(status);
+    for header in headers {
+        if let Ok(header) = header.parse::<Header>() {
+            response.add_header(header);
+        }
+    }
+    if let Err(e) = request.respond(response) {
+        debug!("发送响应失败: {}", e);
+    }
+}
+
+pub fn run(args: ServeArgs) -> Result<()> {
+    let mut catalogue = Catalogue::load(&args)?;
+    let server = Server::http(&args.listen)
+        .map_err(|e| anyhow::anyhow!("无法监听 {}: {}", args.listen, e))?;
+    info!("HTTP服务已启动: http://{}", args.listen);
+
+    for request in server.incoming_requests() {
+        debug!("{} {}", request.method(), request.url());
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            let body = serde_json::json!({ "error": "只支持GET请求" }).to_string();
+            respond(request, 405, body);
+            continue;
+        }
+
+        catalogue.refresh(&args);
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        match route(&catalogue, path, query) {
+            Ok(body) => respond(request, 200, body),
+            Err(e) => {
+                let body = serde_json::json!({ "error": e.message }).to_string();
+                respond(request, e.status, body);
+            }
+        }
+    }
+    Ok(())
+}
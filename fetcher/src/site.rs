@@ -0,0 +1,398 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::info;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    BmsEntry,
+    artists::{self, ArtistProfile, ArtistResolver},
+    catalog,
+    feed::xml_escape,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct SiteArgs {
+    #[command(subcommand)]
+    command: SiteCommand,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum SiteCommand {
+    /// 从 events/*.toml 生成静态HTML站点
+    Build(BuildArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct BuildArgs {
+    /// 输出目录
+    #[arg(short, long, default_value = "site")]
+    output: PathBuf,
+
+    /// 站点标题
+    #[arg(long, default_value = "BOF 作品目录")]
+    title: String,
+
+    /// 事件数据目录
+    #[arg(long, default_value = "events")]
+    events_dir: PathBuf,
+
+    /// 事件配置文件路径，用于读取事件年份
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+
+    /// 作者别名文件
+    #[arg(long, default_value = "artists.toml")]
+    aliases: PathBuf,
+}
+
+const STYLE: &str = r#"body { font-family: sans-serif; margin: 2em auto; max-width: 80em; padding: 0 1em; color: #222; }
+nav { margin-bottom: 1em; }
+nav a { margin-right: 1em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.3em 0.5em; text-align: left; vertical-align: top; }
+th.sortable { cursor: pointer; user-select: none; white-space: nowrap; }
+th.sortable::after { content: " \2195"; color: #aaa; }
+th[aria-sort="ascending"]::after { content: " \2191"; color: #222; }
+th[aria-sort="descending"]::after { content: " \2193"; color: #222; }
+td.num { text-align: right; white-space: nowrap; }
+td.links a { display: inline-block; margin-right: 0.5em; }
+input.filter { margin-bottom: 1em; padding: 0.3em; width: 20em; }
+"#;
+
+/// 点击表头排序，按 data-sort 或单元格文本比较；输入框按行文本过滤
+const SCRIPT: &str = r#"document.querySelectorAll("table.sortable").forEach(function (table) {
+  var headers = table.querySelectorAll("th.sortable");
+  headers.forEach(function (th, column) {
+    th.addEventListener("click", function () {
+      var numeric = th.dataset.type === "number";
+      var ascending = th.getAttribute("aria-sort") !== "ascending";
+      headers.forEach(function (other) { other.removeAttribute("aria-sort"); });
+      th.setAttribute("aria-sort", ascending ? "ascending" : "descending");
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.rows);
+      var value = function (row) {
+        var cell = row.cells[column];
+        var text = cell.dataset.sort !== undefined ? cell.dataset.sort : cell.textContent.trim();
+        return numeric ? (parseFloat(text) || 0) : text;
+      };
+      rows.sort(function (a, b) {
+        var x = value(a), y = value(b);
+        var result = numeric ? x - y : String(x).localeCompare(String(y), "ja");
+        return ascending ? result : -result;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+document.querySelectorAll("input.filter").forEach(function (input) {
+  var table = document.getElementById(input.dataset.table);
+  input.addEventListener("input", function () {
+    var needle = input.value.trim().toLowerCase();
+    Array.prototype.forEach.call(table.tBodies[0].rows, function (row) {
+      row.hidden = needle !== "" && row.textContent.toLowerCase().indexOf(needle) < 0;
+    });
+  });
+});
+"#;
+
+/// 作者页面的文件名：key可能包含任意字符，使用其哈希值
+fn artist_slug(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .take(6)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 页面框架，`root` 为页面到站点根目录的相对路径
+fn page(site_title: &str, title: &str, root: &str, body: &str) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    html.push_str(&format!(
+        "<title>{} - {}</title>\n",
+        xml_escape(title),
+        xml_escape(site_title)
+    ));
+    html.push_str(&format!(
+        "<link rel=\"stylesheet\" href=\"{}style.css\">\n",
+        root
+    ));
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!(
+        "<nav><a href=\"{0}index.html\">{1}</a><a href=\"{0}artists/index.html\">作者</a></nav>\n",
+        root,
+        xml_escape(site_title)
+    ));
+    html.push_str(&format!("<h1>{}</h1>\n", xml_escape(title)));
+    html.push_str(body);
+    html.push_str(&format!(
+        "<script src=\"{}sort.js\"></script>\n</body>\n</html>\n",
+        root
+    ));
+    html
+}
+
+fn render_links(addr: &[String]) -> String {
+    addr.iter()
+        .map(|addr| match catalog::url_host(addr) {
+            Some(host) => format!(
+                "<a href=\"{}\" rel=\"nofollow noopener\">{}</a>",
+                xml_escape(addr),
+                xml_escape(&host)
+            ),
+            None => xml_escape(addr),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn artist_link(resolver: &ArtistResolver, event: &str, name: &str, root: &str) -> String {
+    let (key, _) = resolver.resolve(event, name);
+    if key.is_empty() {
+        return xml_escape(name);
+    }
+    format!(
+        "<a href=\"{}artists/{}.html\">{}</a>",
+        root,
+        artist_slug(&key),
+        xml_escape(name)
+    )
+}
+
+/// 作者栏：团队作品链接到各成员，其他作品链接到作者
+fn render_credit(resolver: &ArtistResolver, event: &str, entry: &BmsEntry) -> String {
+    if entry.members.is_empty() {
+        return artist_link(resolver, event, &entry.name, "../");
+    }
+    entry
+        .members
+        .iter()
+        .map(|member| artist_link(resolver, event, member, "../"))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+fn render_index(
+    args: &BuildArgs,
+    events: &[&catalog::EventFile],
+    years: &HashMap<String, u16>,
+) -> String {
+    let mut body = String::new();
+    body.push_str("<table class=\"sortable\">\n<thead><tr>");
+    body.push_str("<th class=\"sortable\">事件</th>");
+    body.push_str("<th class=\"sortable\" data-type=\"number\">年份</th>");
+    body.push_str("<th class=\"sortable\" data-type=\"number\">作品数</th>");
+    body.push_str("</tr></thead>\n<tbody>\n");
+    for event in events {
+        body.push_str(&format!(
+            "<tr><td><a href=\"events/{0}.html\">{0}</a></td><td class=\"num\">{1}</td><td class=\"num\">{2}</td></tr>\n",
+            xml_escape(&event.key),
+            years.get(&event.key).map(u16::to_string).unwrap_or_default(),
+            event.data.entries.len()
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n");
+    page(&args.title, &args.title, "", &body)
+}
+
+fn render_event(
+    args: &BuildArgs,
+    event: &catalog::EventFile,
+    year: Option<u16>,
+    resolver: &ArtistResolver,
+) -> String {
+    let has_team = event.data.entries.iter().any(|entry| entry.team.is_some());
+
+    let mut body = String::new();
+    if let Some(year) = year {
+        body.push_str(&format!(
+            "<p>{} 年，{} 个作品</p>\n",
+            year,
+            event.data.entries.len()
+        ));
+    } else {
+        body.push_str(&format!("<p>{} 个作品</p>\n", event.data.entries.len()));
+    }
+    body.push_str(
+        "<input class=\"filter\" type=\"search\" placeholder=\"过滤\" data-table=\"entries\">\n",
+    );
+    body.push_str("<table id=\"entries\" class=\"sortable\">\n<thead><tr>");
+    body.push_str("<th class=\"sortable\" data-type=\"number\">No.</th>");
+    body.push_str("<th class=\"sortable\">作者</th>");
+    if has_team {
+        body.push_str("<th class=\"sortable\">团队</th>");
+    }
+    body.push_str("<th class=\"sortable\">标题</th>");
+    body.push_str("<th class=\"sortable\" data-type=\"number\">大小</th>");
+    body.push_str("<th>链接</th>");
+    body.push_str("</tr></thead>\n<tbody>\n");
+
+    for entry in &event.data.entries {
+        body.push_str("<tr>");
+        body.push_str(&format!(
+            "<td class=\"num\" id=\"no-{0}\">{0}</td>",
+            xml_escape(&entry.no)
+        ));
+        body.push_str(&format!(
+            "<td>{}</td>",
+            render_credit(resolver, &event.key, entry)
+        ));
+        if has_team {
+            body.push_str(&format!(
+                "<td>{}</td>",
+                xml_escape(entry.team.as_deref().unwrap_or_default())
+            ));
+        }
+        body.push_str(&format!("<td>{}</td>", xml_escape(&entry.title)));
+        body.push_str(&format!(
+            "<td class=\"num\" data-sort=\"{}\">{}</td>",
+            catalog::parse_size_kb(&entry.size).unwrap_or_default(),
+            xml_escape(&entry.size)
+        ));
+        body.push_str(&format!(
+            "<td class=\"links\">{}</td>",
+            render_links(&entry.addr)
+        ));
+        body.push_str("</tr>\n");
+    }
+    body.push_str("</tbody>\n</table>\n");
+    page(&args.title, &event.key, "../", &body)
+}
+
+fn render_artist(args: &BuildArgs, artist: &ArtistProfile) -> String {
+    let mut body = String::new();
+    if artist.aliases.len() > 1 || artist.aliases.first() != Some(&artist.name) {
+        body.push_str(&format!(
+            "<p>别名: {}</p>\n",
+            artist
+                .aliases
+                .iter()
+                .map(|alias| xml_escape(alias))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    body.push_str("<table class=\"sortable\">\n<thead><tr>");
+    body.push_str("<th class=\"sortable\">事件</th>");
+    body.push_str("<th class=\"sortable\" data-type=\"number\">No.</th>");
+    body.push_str("<th class=\"sortable\">标题</th>");
+    body.push_str("<th class=\"sortable\">作者栏</th>");
+    body.push_str("<th class=\"sortable\">团队</th>");
+    body.push_str("<th>链接</th>");
+    body.push_str("</tr></thead>\n<tbody>\n");
+    for work in &artist.works {
+        body.push_str(&format!(
+            "<tr><td><a href=\"../events/{0}.html\">{0}</a></td><td class=\"num\"><a href=\"../events/{0}.html#no-{1}\">{1}</a></td><td>{2}</td><td>{3}</td><td>{4}</td><td class=\"links\">{5}</td></tr>\n",
+            xml_escape(&work.event),
+            xml_escape(&work.no),
+            xml_escape(&work.title),
+            xml_escape(&work.credited_as),
+            xml_escape(work.team.as_deref().unwrap_or_default()),
+            work.link
+                .as_ref()
+                .map(|link| render_links(std::slice::from_ref(link)))
+                .unwrap_or_default()
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n");
+    page(&args.title, &artist.name, "../", &body)
+}
+
+fn render_artist_index(args: &BuildArgs, artists: &[ArtistProfile]) -> String {
+    let mut body = String::new();
+    body.push_str(
+        "<input class=\"filter\" type=\"search\" placeholder=\"过滤\" data-table=\"artists\">\n",
+    );
+    body.push_str("<table id=\"artists\" class=\"sortable\">\n<thead><tr>");
+    body.push_str("<th class=\"sortable\">作者</th>");
+    body.push_str("<th class=\"sortable\" data-type=\"number\">作品数</th>");
+    body.push_str("<th class=\"sortable\">别名</th>");
+    body.push_str("</tr></thead>\n<tbody>\n");
+    for artist in artists {
+        body.push_str(&format!(
+            "<tr><td><a href=\"{}.html\">{}</a></td><td class=\"num\">{}</td><td>{}</td></tr>\n",
+            artist_slug(&artist.key),
+            xml_escape(&artist.name),
+            artist.works.len(),
+            artist
+                .aliases
+                .iter()
+                .filter(|alias| **alias != artist.name)
+                .map(|alias| xml_escape(alias))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n");
+    page(&args.title, "作者", "../", &body)
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).with_context(|| format!("写入文件失败: {:?}", path))
+}
+
+fn build(args: BuildArgs) -> Result<()> {
+    let resolver = ArtistResolver::load(&args.aliases)?;
+    let events = catalog::load_event_files(&args.events_dir)?;
+    let keys: Vec<&str> = events.iter().map(|e| e.key.as_str()).collect();
+    let years = catalog::event_years(&args.config, &keys);
+
+    let events_dir = args.output.join("events");
+    let artists_dir = args.output.join("artists");
+    for dir in [&events_dir, &artists_dir] {
+        fs::create_dir_all(dir).with_context(|| format!("创建目录失败: {:?}", dir))?;
+    }
+    write_file(&args.output.join("style.css"), STYLE)?;
+    write_file(&args.output.join("sort.js"), SCRIPT)?;
+
+    // 首页按年份从新到旧排列
+    let mut ordered: Vec<&catalog::EventFile> = events.iter().collect();
+    ordered.sort_by_key(|event| {
+        (
+            std::cmp::Reverse(years.get(&event.key).copied()),
+            event.key.clone(),
+        )
+    });
+    write_file(
+        &args.output.join("index.html"),
+        &render_index(&args, &ordered, &years),
+    )?;
+
+    for event in &events {
+        let html = render_event(&args, event, years.get(&event.key).copied(), &resolver);
+        write_file(&events_dir.join(format!("{}.html", event.key)), &html)?;
+    }
+
+    let artists = artists::build_index(&events, &years, &resolver);
+    for artist in &artists {
+        write_file(
+            &artists_dir.join(format!("{}.html", artist_slug(&artist.key))),
+            &render_artist(&args, artist),
+        )?;
+    }
+    write_file(
+        &artists_dir.join("index.html"),
+        &render_artist_index(&args, &artists),
+    )?;
+
+    info!(
+        "生成了 {} 个事件页面和 {} 个作者页面 -> {:?}",
+        events.len(),
+        artists.len(),
+        args.output
+    );
+    Ok(())
+}
+
+pub fn run(args: SiteArgs) -> Result<()> {
+    match args.command {
+        SiteCommand::Build(build_args) => build(build_args),
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use log::{debug, error, info, warn};
+use serde::Serialize;
+
+use crate::{
+    BmsEntry, convert_to_toml, diff, event_url, fetch_and_parse_table, load_events_config,
+    parse_event_data,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct WatchArgs {
+    /// 要监视的事件key (例如: BOF21)
+    #[arg(long)]
+    event: String,
+
+    /// 抓取间隔，支持 s/m/h 后缀 (例如: 30s, 10m, 1h)
+    #[arg(long, default_value = "10m", value_parser = parse_interval)]
+    interval: Duration,
+
+    /// 事件配置文件路径
+    #[arg(long, default_value = "events.toml")]
+    config: PathBuf,
+
+    /// 快照文件路径，默认为 events/<key>.toml
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+
+    /// 每个变化执行的命令，变化以一行JSON的形式写入命令的stdin；不指定则输出到stdout
+    #[arg(long)]
+    hook: Option<String>,
+}
+
+/// 输出的一行JSON
+#[derive(Debug, Serialize)]
+struct WatchEvent<'a> {
+    event: &'a str,
+    detected_at: String,
+    #[serde(flatten)]
+    change: &'a diff::EntryChange,
+}
+
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => value.split_at(idx),
+        None => (value, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("无效的时间间隔: {}", value))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(format!("无效的时间单位: {} (支持 s, m, h)", unit)),
+    };
+    let seconds = number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("无效的时间间隔: {}", value))?;
+    if seconds == 0 {
+        return Err("时间间隔必须大于0".to_string());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+fn load_snapshot(path: &Path) -> Result<Vec<BmsEntry>> {
+    if !path.exists() {
+        warn!("快照文件不存在，首次抓取的所有作品都将视为新增: {:?}", path);
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+    let data =
+        parse_event_data(&content).with_context(|| format!("解析TOML文件失败: {:?}", path))?;
+    Ok(data.entries)
+}
+
+fn run_hook(hook: &str, line: &str) -> Result<()> {
+    #[cfg(windows)]
+    let mut command = Command::new("cmd");
+    #[cfg(windows)]
+    command.arg("/C").arg(hook);
+    #[cfg(not(windows))]
+    let mut command = Command::new("sh");
+    #[cfg(not(windows))]
+    command.arg("-c").arg(hook);
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("无法执行命令: {}", hook))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", line)?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        warn!("命令执行失败 ({}): {}", status, hook);
+    }
+    Ok(())
+}
+
+fn emit(args: &WatchArgs, changes: &[diff::EntryChange]) -> Result<()> {
+    let detected_at = chrono::Local::now().to_rfc3339();
+    for change in changes {
+        let line = serde_json::to_string(&WatchEvent {
+            event: &args.event,
+            detected_at: detected_at.clone(),
+            change,
+        })?;
+        match &args.hook {
+            Some(hook) => {
+                if let Err(e) = run_hook(hook, &line) {
+                    error!("执行hook失败: {}", e);
+                }
+            }
+            None => {
+                println!("{}", line);
+                std::io::stdout().flush()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn run(args: WatchArgs) -> Result<()> {
+    let config = load_events_config(&args.config)?;
+    let event = config
+        .events
+        .iter()
+        .find(|event| event.key == args.event)
+        .ok_or_else(|| anyhow::anyhow!("events.toml中没有找到事件: {}", args.event))?;
+    let url = event_url(event)
+        .ok_or_else(|| anyhow::anyhow!("事件 {} 既没有 event_id 也没有 url 字段", event.key))?;
+
+    let snapshot_path = args
+        .snapshot
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("events").join(format!("{}.toml", args.event)));
+    let mut snapshot = load_snapshot(&snapshot_path)?;
+
+    info!(
+        "开始监视事件 {}，间隔 {:?}，快照 {:?}",
+        args.event, args.interval, snapshot_path
+    );
+
+    loop {
+        match fetch_and_parse_table(&url).await {
+            Ok(data) if data.entries.is_empty() => {
+                // 页面暂时无法访问时可能返回空表格，不应视为全部删除
+                warn!("没有解析到任何作品，跳过本次比较");
+            }
+            Ok(data) => {
+                let changes = diff::diff_entries(&snapshot, &data.entries);
+                if changes.is_empty() {
+                    debug!("没有变化");
+                } else {
+                    info!("检测到 {} 个变化", changes.len());
+                    emit(&args, &changes)?;
+
+                    let toml_output = convert_to_toml(&data)?;
+                    fs::write(&snapshot_path, toml_output)
+                        .with_context(|| format!("保存快照失败: {:?}", snapshot_path))?;
+                    snapshot = data.entries;
+                }
+            }
+            Err(e) => {
+                error!("抓取失败 {}: {}", url, e);
+            }
+        }
+
+        smol::Timer::after(args.interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse_interval;
+
+    #[test]
+    fn units() {
+        assert_eq!(parse_interval("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_interval("45s"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_interval("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_interval(" 2h "), Ok(Duration::from_secs(7200)));
+        assert!(parse_interval("5d").is_err());
+        assert!(parse_interval("m").is_err());
+        assert!(parse_interval("").is_err());
+    }
+
+    #[test]
+    fn zero() {
+        assert!(parse_interval("0").is_err());
+        assert!(parse_interval("0m").is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        assert!(parse_interval(&format!("{}h", u64::MAX / 60)).is_err());
+        assert!(parse_interval("99999999999999999999").is_err());
+        assert_eq!(
+            parse_interval(&format!("{}s", u64::MAX)),
+            Ok(Duration::from_secs(u64::MAX))
+        );
+    }
+}